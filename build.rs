@@ -0,0 +1,62 @@
+//! Generates the list of embedded xwing-data2 files when the `embedded`
+//! feature is enabled.
+//!
+//! `include_dir`-style macros fail the whole build when the submodule hasn't
+//! been checked out, so this just writes out a table of `include_str!`s and
+//! warns if there was nothing to include.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+fn collect_json(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_json(root, &path, files)?;
+        } else if path.extension().is_some_and(|e| e == "json") {
+            // manifest paths always use `/`, regardless of platform
+            let relative = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path.canonicalize()?));
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("xwing_data2.rs");
+
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let mut files = vec![];
+    if env::var_os("CARGO_FEATURE_EMBEDDED").is_some() {
+        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("xwing-data2");
+        println!("cargo:rerun-if-changed={}", root.join("data").display());
+
+        if let Err(e) = collect_json(&root, &root.join("data"), &mut files) {
+            println!(
+                "cargo:warning=xwing-data2 not embedded ({}), run `git submodule update --init`",
+                e
+            );
+        }
+        files.sort();
+    }
+
+    let mut src = String::from("pub static FILES: &[(&str, &str)] = &[\n");
+    for (relative, path) in files {
+        src.push_str(&format!(
+            "    ({:?}, include_str!({:?})),\n",
+            relative,
+            path.display().to_string()
+        ));
+    }
+    src.push_str("];\n");
+
+    fs::write(out, src).unwrap();
+}
@@ -0,0 +1,107 @@
+//! Legacy ship models that can be used as current ships, per the tournament
+//! regulations, e.g. a 1.0 X-wing is a T-65 X-wing.
+//!
+//! The map is keyed by the legacy model's xws id, like the `-legacyyasb`
+//! ships left in older YASB collections, with the xws ids of the ships it can
+//! be used as:
+//!
+//! ```json
+//! {
+//!   "xwing-legacyyasb": ["t65xwing"]
+//! }
+//! ```
+//!
+//! A model usable as more than one ship is counted toward each of them, so
+//! squads fielding several of those ships at once may come up short.
+//!
+//! Only real legacy models belong in the map. YASB also has stale records
+//! for a few current ships, like `E-Wing` next to `E-wing`, and counting
+//! those would count the ship twice, so the YASB name map skips them, see
+//! [`crate::yasb2::NameMap`].
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::expansions::{Item, ItemType, XWS};
+use crate::{Error, Inventory};
+
+/// The `compatibility.json` maintained in this repo.
+#[cfg(feature = "embedded")]
+const EMBEDDED_COMPATIBILITY: &str = include_str!("compatibility.json");
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(transparent)]
+pub struct Compatibility {
+    pub models: BTreeMap<XWS, Vec<XWS>>,
+}
+
+impl Compatibility {
+    /// Loads the `compatibility.json` that was included at build time.
+    #[cfg(feature = "embedded")]
+    pub fn load_embedded() -> Result<Self, Error> {
+        crate::from_json(
+            Path::new("src/compatibility/compatibility.json"),
+            EMBEDDED_COMPATIBILITY,
+        )
+    }
+
+    pub fn load_from_path(path: &Path) -> Result<Self, Error> {
+        let buffer = crate::read_to_string(path)?;
+        crate::from_json(path, &buffer)
+    }
+
+    /// Replaces the legacy ship models in the inventory with the ships they
+    /// can be used as.
+    pub fn apply(&self, inventory: Inventory) -> Inventory {
+        let mut applied = Inventory::new();
+        for (item, count) in inventory {
+            let ships = match self.models.get(&item.xws) {
+                Some(ships) if item.r#type == ItemType::Ship => ships,
+                _ => {
+                    *applied.entry(item).or_insert(0) += count;
+                    continue;
+                }
+            };
+            for xws in ships {
+                *applied
+                    .entry(Item {
+                        r#type: ItemType::Ship,
+                        xws: xws.to_owned(),
+                    })
+                    .or_insert(0) += count;
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let compatibility: Compatibility =
+            serde_json::from_str(r#"{"xwing-legacyyasb": ["t65xwing"]}"#).unwrap();
+        let inventory = Inventory::from([
+            (Item::new(ItemType::Ship, "xwing-legacyyasb"), 2),
+            (Item::new(ItemType::Ship, "t65xwing"), 1),
+            (Item::new(ItemType::Ship, "tielnfighter"), 1),
+        ]);
+
+        assert_eq!(
+            compatibility.apply(inventory),
+            Inventory::from([
+                (Item::new(ItemType::Ship, "t65xwing"), 3),
+                (Item::new(ItemType::Ship, "tielnfighter"), 1)
+            ])
+        );
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_embedded() {
+        let compatibility = Compatibility::load_embedded().unwrap();
+        assert_eq!(compatibility.models["xwing-legacyyasb"], vec!["t65xwing"]);
+    }
+}
@@ -0,0 +1,116 @@
+//! The physical components that aren't cards or models, since a ship can't
+//! be put on the table without its dial, and they're easy to lose.
+//!
+//! The xws ids of the component types are:
+//!
+//! - `dial`: the xws of the ship the dial is for, e.g. `t65xwing`
+//! - `template`: one of [`TEMPLATES`]
+//!
+//! Each ship pack in the catalog lists the dials for its ships, and the
+//! conversion kits list dials without ships.
+//!
+//! Bases, pegs and tokens aren't tracked. Every model comes on its base and
+//! peg, and the number of ID, shield, charge and force tokens differs per
+//! product in ways xwing-data2 doesn't record, so they couldn't be listed in
+//! the catalog reliably.
+use crate::expansions::{Item, ItemType};
+use crate::xwingdata2::Data;
+use crate::Inventory;
+
+/// The templates, as xws and display name.
+pub const TEMPLATES: [(&str, &str); 2] = [
+    ("rangeruler", "Range Ruler"),
+    ("maneuvertemplates", "Maneuver Templates"),
+];
+
+/// The display name of a component, or `None` if it isn't a known component.
+pub fn name(item: &Item, data: &Data) -> Option<String> {
+    let lookup = |list: &[(&str, &str)]| {
+        list.iter()
+            .find(|(xws, _)| *xws == item.xws)
+            .map(|(_, name)| name.to_string())
+    };
+    match item.r#type {
+        ItemType::Dial => data
+            .get_ship_model(&item.xws)
+            .map(|s| format!("{} Dial", s.name)),
+        ItemType::Template => lookup(&TEMPLATES),
+        _ => None,
+    }
+}
+
+/// The number of ship models in the inventory that a dial is for. It's 0 for
+/// everything else.
+pub fn ships(item: &Item, inventory: &Inventory) -> u32 {
+    match item.r#type {
+        ItemType::Dial => inventory
+            .iter()
+            .filter(|(i, _)| i.r#type == ItemType::Ship && i.xws == item.xws)
+            .map(|(_, c)| c)
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Every component in the inventory, and a dial for every ship model
+/// without one, so the missing dials show up.
+pub fn items(inventory: &Inventory) -> Vec<Item> {
+    let mut items: Vec<Item> = inventory
+        .keys()
+        .filter(|i| i.r#type.is_component())
+        .cloned()
+        .collect();
+    for i in inventory.keys().filter(|i| i.r#type == ItemType::Ship) {
+        let dial = Item {
+            r#type: ItemType::Dial,
+            xws: i.xws.to_owned(),
+        };
+        if !inventory.contains_key(&dial) {
+            items.push(dial);
+        }
+    }
+    items.sort();
+    items
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xwingdata2::fixtures::ship;
+
+    #[test]
+    fn test_dials() {
+        let data = Data {
+            ships: vec![ship(
+                "TIE/ln Fighter",
+                "tielnfighter",
+                "galacticempire",
+                vec![],
+            )],
+            ..Default::default()
+        };
+        let inventory = Inventory::from([
+            (Item::new(ItemType::Ship, "tielnfighter"), 3),
+            (Item::new(ItemType::Ship, "t65xwing"), 1),
+            (Item::new(ItemType::Dial, "tielnfighter"), 2),
+            (Item::new(ItemType::Template, "rangeruler"), 1),
+        ]);
+
+        let dial = Item::new(ItemType::Dial, "tielnfighter");
+        assert_eq!(
+            items(&inventory),
+            vec![
+                Item::new(ItemType::Dial, "t65xwing"),
+                dial.clone(),
+                Item::new(ItemType::Template, "rangeruler"),
+            ]
+        );
+        assert_eq!(name(&dial, &data).unwrap(), "TIE/ln Fighter Dial");
+        assert_eq!(ships(&dial, &inventory), 3);
+        assert_eq!(
+            ships(&Item::new(ItemType::Template, "rangeruler"), &inventory),
+            0
+        );
+        assert!(name(&Item::new(ItemType::Template, "ion"), &data).is_none());
+    }
+}
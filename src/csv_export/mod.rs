@@ -0,0 +1,314 @@
+//! Writes the [`Records`] as csv, with the columns in the same order as the
+//! tables in the generated spreadsheet.
+//!
+//! There are no `Singles` columns, since the records only have the total
+//! count. The comma joined columns, like `Sources`, are quoted as needed.
+//!
+//! The long format puts every type in a single file, with one row per
+//! column of each record, e.g.
+//! `pilot,lukeskywalker,Luke Skywalker,1,Faction,Rebel Alliance`.
+use std::io::Write;
+
+use crate::{
+    ComponentRecord, DamageDeckRecord, Error, ObstacleRecord, PilotRecord, Records, ShipRecord,
+    UpgradeRecord,
+};
+
+/// A record that can be written as a csv row.
+pub trait CsvRecord {
+    /// The column headers, matching the spreadsheet table.
+    const HEADERS: &'static [&'static str];
+    /// The type column in the long format.
+    const TYPE: &'static str;
+
+    fn row(&self) -> Vec<String>;
+}
+
+impl CsvRecord for ShipRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "Name",
+        "Total",
+        "Size",
+        "Factions",
+        "Attack",
+        "Agility",
+        "Hull",
+        "Shields",
+        "Actions",
+        "Dial Codes",
+        "XWS",
+        "Sources",
+        "Ship Ability",
+    ];
+    const TYPE: &'static str = "ship";
+
+    fn row(&self) -> Vec<String> {
+        let stat = |s: Option<u32>| s.map(|v| v.to_string()).unwrap_or_default();
+        vec![
+            self.name.to_owned(),
+            self.count.to_string(),
+            self.size.to_owned(),
+            self.factions.to_owned(),
+            self.attack.to_owned(),
+            stat(self.agility),
+            stat(self.hull),
+            stat(self.shields),
+            self.actions.to_owned(),
+            self.dial_codes.to_owned(),
+            self.xws.to_owned(),
+            self.sources.to_owned().unwrap_or_default(),
+            self.ship_ability.to_owned().unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRecord for PilotRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "Name",
+        "Ship",
+        "Caption",
+        "Total",
+        "Faction",
+        "Initiative",
+        "Limited",
+        "Cost",
+        "Loadout",
+        "Standard Loadout",
+        "Slots",
+        "Charges",
+        "Force",
+        "Keywords",
+        "XWS",
+        "Sources",
+        "Ability",
+        "Ship Ability",
+    ];
+    const TYPE: &'static str = "pilot";
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            self.ship.to_owned(),
+            self.caption.to_owned().unwrap_or_default(),
+            self.count.to_string(),
+            self.faction.to_owned(),
+            self.initiative.to_string(),
+            self.limited.to_string(),
+            self.cost.map(|c| c.to_string()).unwrap_or_default(),
+            self.loadout.map(|l| l.to_string()).unwrap_or_default(),
+            self.standard_loadout.to_string(),
+            self.slots.to_owned(),
+            self.charges
+                .as_ref()
+                .map(|c| c.value.to_string())
+                .unwrap_or_default(),
+            self.force
+                .as_ref()
+                .map(|f| f.value.to_string())
+                .unwrap_or_default(),
+            self.keywords.to_owned(),
+            self.xws.to_owned(),
+            self.sources.to_owned().unwrap_or_default(),
+            self.ability.to_owned().unwrap_or_default(),
+            self.ship_ability.to_owned().unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRecord for UpgradeRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "Name",
+        "Type",
+        "Total",
+        "Faction Restriction",
+        "Slots",
+        "All Slot Types",
+        "Ship Restriction",
+        "Size Restriction",
+        "Arc Restriction",
+        "Force Side Restriction",
+        "Keyword Restriction",
+        "Restrictions",
+        "XWS",
+        "Sources",
+        "Charges",
+        "Ability",
+    ];
+    const TYPE: &'static str = "upgrade";
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            self.r#type.to_owned(),
+            self.count.to_string(),
+            self.faction_restriction.to_owned(),
+            self.slots.to_owned(),
+            self.all_slot_types.to_owned(),
+            self.ship_restriction.to_owned(),
+            self.size_restriction.to_owned(),
+            self.arc_restriction.to_owned(),
+            self.force_side_restriction.to_owned(),
+            self.keyword_restriction.to_owned(),
+            self.restrictions.to_owned(),
+            self.xws.to_owned(),
+            self.sources.to_owned().unwrap_or_default(),
+            self.charges
+                .as_ref()
+                .map(|c| c.value.to_string())
+                .unwrap_or_default(),
+            self.ability.to_owned().unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRecord for ObstacleRecord {
+    const HEADERS: &'static [&'static str] = &["Name", "Type", "Total", "XWS", "Sources"];
+    const TYPE: &'static str = "obstacle";
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            self.r#type.to_owned(),
+            self.count.to_string(),
+            self.xws.to_owned(),
+            self.sources.to_owned().unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRecord for DamageDeckRecord {
+    const HEADERS: &'static [&'static str] =
+        &["Name", "Deck", "Type", "Amount", "Total", "XWS", "Sources"];
+    const TYPE: &'static str = "damage";
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            self.deck.to_owned(),
+            self.r#type.to_owned(),
+            self.amount.to_string(),
+            self.count.to_string(),
+            self.xws.to_owned(),
+            self.sources.to_owned().unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRecord for ComponentRecord {
+    const HEADERS: &'static [&'static str] = &["Name", "Type", "Total", "Ships", "XWS", "Sources"];
+    const TYPE: &'static str = "component";
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            self.r#type.as_ref().to_owned(),
+            self.count.to_string(),
+            self.ships.to_string(),
+            self.xws.to_owned(),
+            self.sources.to_owned().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Writes one csv file of records with a header row.
+pub fn write<T: CsvRecord, W: Write>(records: &[T], writer: W) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(T::HEADERS)?;
+    for r in records {
+        writer.write_record(r.row())?;
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+const LONG_HEADERS: [&str; 6] = ["Type", "XWS", "Name", "Total", "Column", "Value"];
+
+/// Writes all the records as a single csv file in the long format, leaving
+/// out the empty values.
+pub fn write_long<W: Write>(records: &Records, writer: W) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(LONG_HEADERS)?;
+    write_long_rows(&mut writer, &records.ships)?;
+    write_long_rows(&mut writer, &records.pilots)?;
+    write_long_rows(&mut writer, &records.upgrades)?;
+    write_long_rows(&mut writer, &records.obstacles)?;
+    write_long_rows(&mut writer, &records.damage_decks)?;
+    write_long_rows(&mut writer, &records.components)?;
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+fn write_long_rows<T: CsvRecord, W: Write>(
+    writer: &mut csv::Writer<W>,
+    records: &[T],
+) -> Result<(), Error> {
+    let column = |name| T::HEADERS.iter().position(|h| *h == name).unwrap();
+    let (xws, name, total) = (column("XWS"), column("Name"), column("Total"));
+
+    for r in records {
+        let row = r.row();
+        for (i, value) in row.iter().enumerate() {
+            if i == xws || i == name || i == total || value.is_empty() {
+                continue;
+            }
+            writer.write_record([
+                T::TYPE,
+                &row[xws],
+                &row[name],
+                &row[total],
+                T::HEADERS[i],
+                value,
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ship() -> ShipRecord {
+        ShipRecord {
+            name: "T-65 X-wing".to_owned(),
+            xws: "t65xwing".to_owned(),
+            size: "Small".to_owned(),
+            factions: "Rebel Alliance".to_owned(),
+            attack: "Front Arc 3".to_owned(),
+            agility: Some(2),
+            hull: Some(4),
+            shields: Some(2),
+            actions: "Focus, Lock, Barrel Roll".to_owned(),
+            dial: String::new(),
+            dial_codes: String::new(),
+            count: 2,
+            sources: Some("Core Set:swz01:wave0:1,X-wing:swz06:wave1:1".to_owned()),
+            ship_ability: None,
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let mut buffer = vec![];
+        write(&[ship()], &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Name,Total,Size,Factions,Attack,Agility,Hull,Shields,Actions,Dial Codes,XWS,Sources,Ship Ability\n\
+             T-65 X-wing,2,Small,Rebel Alliance,Front Arc 3,2,4,2,\"Focus, Lock, Barrel Roll\",,t65xwing,\"Core Set:swz01:wave0:1,X-wing:swz06:wave1:1\",\n"
+        );
+    }
+
+    #[test]
+    fn test_write_long() {
+        let records = Records {
+            ships: vec![ship()],
+            ..Default::default()
+        };
+        let mut buffer = vec![];
+        write_long(&records, &mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[1], "ship,t65xwing,T-65 X-wing,2,Size,Small");
+    }
+}
@@ -0,0 +1,135 @@
+//! The differences between two collections, e.g. before and after a trade or
+//! between a YASB dump and a filled in spreadsheet.
+//!
+//! Expansions are compared by SKU and the items by the full inventory, so an
+//! item that moved from an expansion to a single doesn't show up as a change.
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use crate::compatibility::Compatibility;
+use crate::expansions::{Catalog, ItemType, SKU, XWS};
+use crate::{Collection, Inventory};
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SkuDelta {
+    pub sku: SKU,
+    pub before: u32,
+    pub after: u32,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ItemDelta {
+    pub r#type: ItemType,
+    pub xws: XWS,
+    pub before: u32,
+    pub after: u32,
+}
+
+/// Only the SKUs and items with different counts are included.
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct Diff {
+    pub skus: Vec<SkuDelta>,
+    pub items: Vec<ItemDelta>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.skus.is_empty() && self.items.is_empty()
+    }
+}
+
+/// The changes needed to go from collection `a` to collection `b`.
+pub fn diff(
+    a: &Collection,
+    b: &Collection,
+    catalog: &Catalog,
+    compatibility: &Compatibility,
+) -> Diff {
+    let mut diff = Diff::default();
+
+    let skus: BTreeSet<&SKU> = a.skus.keys().chain(b.skus.keys()).collect();
+    for sku in skus {
+        let (before, after) = (
+            *a.skus.get(sku).unwrap_or(&0),
+            *b.skus.get(sku).unwrap_or(&0),
+        );
+        if before != after {
+            diff.skus.push(SkuDelta {
+                sku: sku.to_owned(),
+                before,
+                after,
+            });
+        }
+    }
+
+    let (a_inventory, _) = a.inventory(catalog, compatibility);
+    let (b_inventory, _) = b.inventory(catalog, compatibility);
+    diff.items = item_deltas(&a_inventory, &b_inventory);
+
+    diff
+}
+
+fn item_deltas(a: &Inventory, b: &Inventory) -> Vec<ItemDelta> {
+    let items: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let (before, after) = (*a.get(item).unwrap_or(&0), *b.get(item).unwrap_or(&0));
+            (before != after).then(|| ItemDelta {
+                r#type: item.r#type,
+                xws: item.xws.to_owned(),
+                before,
+                after,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::expansions::Item;
+
+    #[test]
+    fn test_diff() {
+        let catalog = Catalog::from_json(
+            Path::new("test.json"),
+            r#"[{"sku": "xwing", "name": "X-Wing", "wave": 1, "contents": [
+                {"type": "ship", "xws": "t65xwing", "count": 1},
+                {"type": "pilot", "xws": "lukeskywalker", "count": 1}
+            ]}]"#,
+        )
+        .unwrap();
+        let luke = Item {
+            r#type: ItemType::Pilot,
+            xws: "lukeskywalker".to_owned(),
+        };
+
+        let mut a = Collection::default();
+        a.add_expansion("xwing", 1);
+        let mut b = Collection::default();
+        b.add_single(luke, 1);
+
+        let d = diff(&a, &b, &catalog, &Compatibility::default());
+        assert_eq!(
+            d.skus,
+            vec![SkuDelta {
+                sku: "xwing".to_owned(),
+                before: 1,
+                after: 0,
+            }]
+        );
+        assert_eq!(
+            d.items,
+            vec![ItemDelta {
+                r#type: ItemType::Ship,
+                xws: "t65xwing".to_owned(),
+                before: 1,
+                after: 0,
+            }]
+        );
+        assert!(diff(&a, &a, &catalog, &Compatibility::default()).is_empty());
+    }
+}
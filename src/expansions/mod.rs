@@ -8,6 +8,7 @@
 //!  {
 //!    "name": "T-70 X-Wing Expansion Pack",
 //!    "sku": "swz25",
+//!    "kind": "ship_pack",
 //!    "contents": [
 //!    {
 //!        "count": 1,
@@ -33,7 +34,8 @@
 //!  },
 //!  {
 //!    "name": "Unreleased for 2nd Edition",
-//!    "sku": "swzunreleased"
+//!    "sku": "swzunreleased",
+//!    "kind": "placeholder"
 //!   }
 //! }
 //!```
@@ -41,7 +43,7 @@
 //!```rust
 //!use xwingtmg2_inventory_rs::expansions::Catalog;
 //!
-//!let catalog = Catalog::load().unwrap();
+//!let catalog = Catalog::load_embedded().unwrap();
 //!let core = catalog.expansions.get("swz01").unwrap();
 //!```
 //!
@@ -50,21 +52,54 @@
 //! - Even though sku's are unique enough for this to be a map, storing as a
 //!   list makes it easier to keep sorted in the json.
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fs, io};
+use std::{collections::BTreeMap, path::Path};
+use strum::{AsRefStr, EnumString};
+
+use crate::Error;
+
+/// The `expansions.json` maintained in this repo.
+#[cfg(feature = "embedded")]
+const EMBEDDED_EXPANSIONS: &str = include_str!("expansions.json");
 
 /// Type literals used in the serialized format.
-#[derive(Deserialize, Serialize, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Debug)]
+#[derive(
+    Deserialize,
+    Serialize,
+    EnumString,
+    AsRefStr,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Copy,
+    Clone,
+    Debug,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
 pub enum ItemType {
-    #[serde(alias = "ship")]
+    #[serde(alias = "Ship")]
     Ship,
-    #[serde(alias = "obstacle")]
+    #[serde(alias = "Obstacle")]
     Obstacle,
-    #[serde(alias = "pilot")]
+    #[serde(alias = "Pilot")]
     Pilot,
-    #[serde(alias = "upgrade")]
+    #[serde(alias = "Upgrade")]
     Upgrade,
-    #[serde(alias = "damage")]
+    #[serde(alias = "Damage")]
     Damage,
+    #[serde(alias = "Dial")]
+    Dial,
+    #[serde(alias = "Template")]
+    Template,
+}
+
+impl ItemType {
+    /// The physical components, see [`crate::components`].
+    pub fn is_component(&self) -> bool {
+        matches!(self, ItemType::Dial | ItemType::Template)
+    }
 }
 
 /// Syntactic sugar for knowing when an xws id is intended to be used.
@@ -79,6 +114,15 @@ pub struct Item {
     pub xws: XWS,
 }
 
+impl Item {
+    pub fn new(r#type: ItemType, xws: &str) -> Self {
+        Self {
+            r#type,
+            xws: xws.to_owned(),
+        }
+    }
+}
+
 /// An association between an Item and it's count that is mostly useful for
 /// de/serialization.
 #[derive(Deserialize, Serialize, Debug)]
@@ -94,6 +138,49 @@ pub struct ItemCount {
 /// interpretation, duplicative, etc., so don't make good ids.
 pub type SKU = String;
 
+/// The kind of product an expansion is.
+///
+/// Conversion kits and card packs only have cards (and dials) for models from
+/// other products, while some older products, like the 1.0 core sets, only
+/// have models.
+#[derive(
+    Deserialize,
+    Serialize,
+    EnumString,
+    AsRefStr,
+    Default,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Copy,
+    Clone,
+    Debug,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ProductKind {
+    Core,
+    #[default]
+    ShipPack,
+    ConversionKit,
+    CardPack,
+    SquadronPack,
+    Epic,
+    /// Scenario and obstacle packs.
+    ScenarioPack,
+    /// Not a product that can be bought, like the `swzunreleased` list of
+    /// ships that haven't been released for 2.0.
+    Placeholder,
+}
+
+impl ProductKind {
+    /// Whether it can be bought, so should be in the purchase reports.
+    pub fn is_product(&self) -> bool {
+        *self != ProductKind::Placeholder
+    }
+}
+
 /// Basic expansion metadata
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(tag = "type")]
@@ -101,6 +188,9 @@ pub struct Expansion {
     pub sku: SKU,
     pub name: String,
     pub wave: u32,
+    /// Defaults to a ship pack.
+    #[serde(default)]
+    pub kind: ProductKind,
     pub contents: Vec<ItemCount>,
 }
 
@@ -129,11 +219,23 @@ impl Catalog {
         false
     }
 
-    pub fn load() -> Result<Self, io::Error> {
-        //TODO: embed with rust-embed or include_bytes! or something
-        let buffer = fs::read_to_string("./src/expansions/expansions.json")?;
+    /// Loads the `expansions.json` that was included at build time.
+    #[cfg(feature = "embedded")]
+    pub fn load_embedded() -> Result<Self, Error> {
+        Self::from_json(
+            Path::new("src/expansions/expansions.json"),
+            EMBEDDED_EXPANSIONS,
+        )
+    }
+
+    /// Loads an `expansions.json` style list of expansions from `path`.
+    pub fn load_from_path(path: &Path) -> Result<Self, Error> {
+        let buffer = crate::read_to_string(path)?;
+        Self::from_json(path, &buffer)
+    }
 
-        let mut list: Vec<Expansion> = serde_json::from_str(&buffer)?;
+    pub(crate) fn from_json(file: &Path, buffer: &str) -> Result<Self, Error> {
+        let mut list: Vec<Expansion> = crate::from_json(file, buffer)?;
 
         let mut catalog = Catalog {
             ..Default::default()
@@ -169,10 +271,7 @@ impl Catalog {
                 .insert(expansion.sku.to_owned(), expansion)
                 .is_some()
             {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("duplicate sku: {}", sku),
-                ));
+                return Err(Error::DuplicateSku(sku));
             }
         }
 
@@ -182,7 +281,7 @@ impl Catalog {
 
 #[cfg(test)]
 mod test {
-    use std::{io::Write, path::Path};
+    use std::io::{self, Write};
 
     use super::*;
     use crate::xwingdata2::known_missing;
@@ -191,7 +290,7 @@ mod test {
     #[test]
     fn test_valid_xws() {
         // checks if all the contents are valid xwsdata
-        let cat = Catalog::load().unwrap();
+        let cat = Catalog::load_from_path(Path::new("./src/expansions/expansions.json")).unwrap();
 
         let d = Data::load_from_manifest(Path::new("xwing-data2")).unwrap();
 
@@ -204,7 +303,9 @@ mod test {
                     ItemType::Ship => d.get_ship_model(&item_count.item.xws).is_some(),
                     ItemType::Pilot => d.get_pilot(&item_count.item.xws).is_some(),
                     ItemType::Upgrade => d.get_upgrade(&item_count.item.xws).is_some(),
-                    _ => continue,
+                    ItemType::Obstacle => d.get_obstacle(&item_count.item.xws).is_some(),
+                    ItemType::Damage => d.get_damage_card(&item_count.item.xws).is_some(),
+                    _ => crate::components::name(&item_count.item, &d).is_some(),
                 };
 
                 println!("{:?}", item_count);
@@ -215,16 +316,70 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_duplicate_sku() {
+        let json = r#"[
+            {"sku": "swz25", "name": "T-70", "wave": 4, "contents": []},
+            {"sku": "swz25", "name": "T-70 again", "wave": 4, "contents": []}
+        ]"#;
+
+        match Catalog::from_json(Path::new("test.json"), json) {
+            Err(Error::DuplicateSku(sku)) => assert_eq!(sku, "swz25"),
+            _ => panic!("expected a duplicate sku error"),
+        }
+    }
+
+    #[test]
+    fn test_kind() {
+        let json = r#"[
+            {"sku": "swz07", "name": "Kit", "wave": 1, "kind": "conversion_kit", "contents": []},
+            {"sku": "swz25", "name": "T-70", "wave": 4, "contents": []}
+        ]"#;
+
+        let catalog = Catalog::from_json(Path::new("test.json"), json).unwrap();
+        assert_eq!(catalog.expansions["swz07"].kind, ProductKind::ConversionKit);
+        assert_eq!(catalog.expansions["swz25"].kind, ProductKind::ShipPack);
+
+        let cat = Catalog::load_from_path(Path::new("./src/expansions/expansions.json")).unwrap();
+        assert_eq!(
+            cat.expansions["swzunreleased"].kind,
+            ProductKind::Placeholder
+        );
+        assert!(!ProductKind::Placeholder.is_product());
+    }
+
+    #[test]
+    fn test_conversion_kit_dials() {
+        // kits come with cards and dials, but no models
+        let cat = Catalog::load_from_path(Path::new("./src/expansions/expansions.json")).unwrap();
+
+        for e in cat.expansions.values() {
+            if e.kind != ProductKind::ConversionKit {
+                continue;
+            }
+            assert!(
+                e.contents.iter().any(|c| c.item.r#type == ItemType::Dial),
+                "no dials in {}",
+                e.sku
+            );
+            assert!(
+                e.contents.iter().all(|c| c.item.r#type != ItemType::Ship),
+                "ship models in {}",
+                e.sku
+            );
+        }
+    }
+
     #[test]
     fn test_for_missing_ships() {
         // checks if all the contents are valid xwsdata
-        let cat = Catalog::load().unwrap();
+        let cat = Catalog::load_from_path(Path::new("./src/expansions/expansions.json")).unwrap();
 
         let data = Data::load_from_manifest(Path::new("xwing-data2")).unwrap();
 
         for s in &data.ships {
             let mut found = false;
-            'search: for (_, e) in &cat.expansions {
+            'search: for e in cat.expansions.values() {
                 for i in &e.contents {
                     if i.item.r#type == ItemType::Ship && i.item.xws == s.xws {
                         found = true;
@@ -0,0 +1,170 @@
+//! Compares the ship models in an inventory against the pilot cards for
+//! them, to find the pilots that can't be fielded without a model and the
+//! models that can't be fielded in a faction without a pilot card.
+//!
+//! This mostly happens with products that only have one or the other, like
+//! 1.0 models without a conversion kit, or conversion kit and card pack pilots
+//! for ships that aren't owned.
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::expansions::{ItemType, XWS};
+use crate::xwingdata2::Data;
+use crate::Inventory;
+
+/// The models and pilot cards owned for a ship.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ShipGap {
+    pub xws: XWS,
+    pub name: String,
+    pub models: u32,
+    /// The number of pilot cards owned per faction display name.
+    pub cards: BTreeMap<String, u32>,
+    /// The factions the ship is in without any pilot cards owned, only when
+    /// there are models.
+    pub factions_without_cards: Vec<String>,
+    /// The pilot cards owned for the ship, only when there are no models.
+    pub unusable_pilots: Vec<XWS>,
+}
+
+impl ShipGap {
+    /// Whether anything owned for the ship can't be fielded.
+    pub fn is_gap(&self) -> bool {
+        !self.factions_without_cards.is_empty() || !self.unusable_pilots.is_empty()
+    }
+}
+
+/// Every ship with models or pilot cards in the inventory, sorted by xws.
+pub fn build(inventory: &Inventory, data: &Data) -> Vec<ShipGap> {
+    let faction_name = |xws: &str| {
+        data.get_faction(xws)
+            .map_or(xws.to_owned(), |f| f.name.to_owned())
+    };
+
+    let mut models: BTreeMap<&str, u32> = BTreeMap::new();
+    // ship xws to (faction, pilot xws, count)
+    let mut pilots: BTreeMap<&str, Vec<(&str, &str, u32)>> = BTreeMap::new();
+    for (item, count) in inventory {
+        if *count == 0 {
+            continue;
+        }
+        match item.r#type {
+            ItemType::Ship => *models.entry(&item.xws).or_insert(0) += count,
+            ItemType::Pilot => {
+                if let Some((ship, pilot)) = data.get_pilot(&item.xws) {
+                    pilots
+                        .entry(&ship.xws)
+                        .or_default()
+                        .push((&ship.faction, &pilot.xws, *count));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut ships: Vec<&str> = models.keys().chain(pilots.keys()).copied().collect();
+    ships.sort();
+    ships.dedup();
+
+    ships
+        .into_iter()
+        .map(|xws| {
+            let models = *models.get(xws).unwrap_or(&0);
+            let mut gap = ShipGap {
+                xws: xws.to_owned(),
+                name: data.get_ship_name(xws).unwrap_or(xws).to_owned(),
+                models,
+                cards: BTreeMap::new(),
+                factions_without_cards: vec![],
+                unusable_pilots: vec![],
+            };
+
+            for (faction, pilot, count) in pilots.get(xws).into_iter().flatten() {
+                *gap.cards.entry(faction_name(faction)).or_insert(0) += count;
+                if models == 0 {
+                    gap.unusable_pilots.push(pilot.to_string());
+                }
+            }
+            if models > 0 {
+                for s in data.ships.iter().filter(|s| s.xws == xws) {
+                    let faction = faction_name(&s.faction);
+                    if !gap.cards.contains_key(&faction) {
+                        gap.factions_without_cards.push(faction);
+                    }
+                }
+            }
+            gap
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::expansions::Catalog;
+    use crate::xwingdata2::fixtures::{pilot, ship};
+    use crate::xwingdata2::Faction;
+    use crate::Collection;
+
+    #[test]
+    fn test_gaps() {
+        let catalog = Catalog::from_json(
+            Path::new("test.json"),
+            r#"[
+                {"sku": "swx01", "name": "Core", "wave": 0, "kind": "core", "contents": [
+                    {"type": "ship", "xws": "t65xwing", "count": 1},
+                    {"type": "ship", "xws": "z95af4headhunter", "count": 1}
+                ]},
+                {"sku": "swz07", "name": "Empire Kit", "wave": 1, "kind": "conversion_kit",
+                 "contents": [{"type": "pilot", "xws": "blacksquadronace", "count": 2}]},
+                {"sku": "swz08", "name": "Scum Kit", "wave": 1, "kind": "conversion_kit",
+                 "contents": [{"type": "pilot", "xws": "binayrepirate", "count": 1}]}
+            ]"#,
+        )
+        .unwrap();
+        let data = Data {
+            ships: vec![
+                ship("T-65 X-wing", "t65xwing", "rebelalliance", vec![]),
+                ship(
+                    "TIE/ln Fighter",
+                    "tielnfighter",
+                    "galacticempire",
+                    vec![pilot("blacksquadronace")],
+                ),
+                ship("Z-95", "z95af4headhunter", "rebelalliance", vec![]),
+                ship(
+                    "Z-95",
+                    "z95af4headhunter",
+                    "scumandvillainy",
+                    vec![pilot("binayrepirate")],
+                ),
+            ],
+            factions: vec![Faction {
+                xws: "rebelalliance".to_owned(),
+                name: "Rebel Alliance".to_owned(),
+            }],
+            ..Default::default()
+        };
+
+        let mut c = Collection::default();
+        c.add_expansion("swx01", 1);
+        c.add_expansion("swz07", 1);
+        c.add_expansion("swz08", 1);
+        let (inventory, _) = c.inventory(&catalog, &Default::default());
+
+        let gaps = build(&inventory, &data);
+        assert_eq!(
+            gaps.iter().map(|g| g.xws.as_str()).collect::<Vec<_>>(),
+            vec!["t65xwing", "tielnfighter", "z95af4headhunter"]
+        );
+        assert_eq!(gaps[0].factions_without_cards, vec!["Rebel Alliance"]);
+        assert_eq!(gaps[1].models, 0);
+        assert_eq!(gaps[1].unusable_pilots, vec!["blacksquadronace"]);
+        assert_eq!(gaps[1].cards["galacticempire"], 2);
+        // usable as scum, but not as rebels
+        assert_eq!(gaps[2].factions_without_cards, vec!["Rebel Alliance"]);
+        assert!(gaps[2].unusable_pilots.is_empty());
+    }
+}
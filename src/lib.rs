@@ -10,23 +10,176 @@
 //! See the project README.md for example usage of the included CLI utility.
 use crate::expansions::Item;
 use crate::xwingdata2::Restriction;
+pub mod compatibility;
+pub mod components;
+pub mod csv_export;
+pub mod diff;
 pub mod expansions;
+pub mod gaps;
+pub mod plan;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod squad;
+pub mod trade;
+pub mod usable;
+pub mod value;
+pub mod xlsx;
 pub mod xwingdata2;
 pub mod yasb2;
 
 use expansions::{Catalog, ItemCount, ItemType, SKU};
 use serde::{Deserialize, Serialize};
-use xwingdata2::Data;
+use xwingdata2::{Charges, Data, Force, SlotKind};
 
 use rust_xlsxwriter::utility::row_col_to_cell;
 use rust_xlsxwriter::{Table, TableColumn, TableFunction, TableStyle, Workbook, XlsxError};
 
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
 
+/// Everything that can go wrong loading data or building the outputs.
 #[derive(Debug)]
-pub enum ErrorKind {
-    NotFound,
+pub enum Error {
+    /// A file couldn't be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// A json document didn't match the expected format. `path` is the
+    /// location of the problem in the document, e.g. `[12].contents[3].type`.
+    Parse {
+        file: PathBuf,
+        path: String,
+        source: serde_json::Error,
+    },
+    /// The type and xws id of an item that isn't in xwing-data2.
+    UnknownXws(Item),
+    /// The same SKU is listed more than once in a catalog.
+    DuplicateSku(SKU),
+    /// A (YASB) count that isn't a non-negative integer.
+    BadCount { name: String, count: String },
+    /// A spreadsheet couldn't be generated.
+    Xlsx(XlsxError),
+    /// A toml document that couldn't be parsed.
+    Toml {
+        file: PathBuf,
+        source: toml::de::Error,
+    },
+    /// A collection couldn't be written as toml.
+    TomlSerialize(toml::ser::Error),
+    /// Something couldn't be written as json.
+    Json(serde_json::Error),
+    /// A SKU that isn't in the catalog.
+    UnknownSku(SKU),
+    /// A spreadsheet that couldn't be read back.
+    XlsxRead(calamine::XlsxError),
+    /// A column that is expected in a spreadsheet table is missing.
+    MissingColumn { table: String, column: String },
+    /// A csv file couldn't be written.
+    Csv(csv::Error),
+    /// The sqlite database couldn't be written.
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse { file, path, source } => {
+                write!(f, "{}: {}: {}", file.display(), path, source)
+            }
+            Error::UnknownXws(item) => write!(f, "{:?} not found: {}", item.r#type, item.xws),
+            Error::DuplicateSku(sku) => write!(f, "duplicate sku: {}", sku),
+            Error::BadCount { name, count } => write!(f, "invalid count for {}: {:?}", name, count),
+            Error::Toml { file, source } => write!(f, "{}: {}", file.display(), source),
+            Error::TomlSerialize(e) => write!(f, "toml: {}", e),
+            Error::Json(e) => write!(f, "json: {}", e),
+            Error::UnknownSku(sku) => write!(f, "unknown sku: {}", sku),
+            Error::Xlsx(e) => write!(f, "xlsx: {}", e),
+            Error::XlsxRead(e) => write!(f, "xlsx: {}", e),
+            Error::MissingColumn { table, column } => {
+                write!(f, "xlsx: table {} is missing column {}", table, column)
+            }
+            Error::Csv(e) => write!(f, "csv: {}", e),
+            #[cfg(feature = "sqlite")]
+            Error::Sqlite(e) => write!(f, "sqlite: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { source, .. } => Some(source),
+            Error::Toml { source, .. } => Some(source),
+            Error::TomlSerialize(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Xlsx(e) => Some(e),
+            Error::XlsxRead(e) => Some(e),
+            Error::Csv(e) => Some(e),
+            #[cfg(feature = "sqlite")]
+            Error::Sqlite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<XlsxError> for Error {
+    fn from(e: XlsxError) -> Self {
+        Error::Xlsx(e)
+    }
+}
+
+impl From<calamine::XlsxError> for Error {
+    fn from(e: calamine::XlsxError) -> Self {
+        Error::XlsxRead(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv(e)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sqlite(e)
+    }
+}
+
+/// Reads a whole file, keeping the path for the error message.
+pub(crate) fn read_to_string(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Reads a toml file if `path` ends in `.toml`, otherwise json.
+pub(crate) fn from_json_or_toml<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, Error> {
+    let buffer = read_to_string(path)?;
+    if is_toml(path) {
+        toml::from_str(&buffer).map_err(|source| Error::Toml {
+            file: path.to_owned(),
+            source,
+        })
+    } else {
+        from_json(path, &buffer)
+    }
+}
+
+/// Deserializes a json document, reporting where in `file` it failed.
+pub(crate) fn from_json<T: for<'a> Deserialize<'a>>(file: &Path, buffer: &str) -> Result<T, Error> {
+    let de = &mut serde_json::Deserializer::from_str(buffer);
+    serde_path_to_error::deserialize(de).map_err(|e| Error::Parse {
+        file: file.to_owned(),
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })
 }
 
 /// A collection is:
@@ -35,12 +188,94 @@ pub enum ErrorKind {
 ///
 /// Minimal error checking is done by the collection itself, it mostly defines
 /// a tool agnostic, unambigous definition of a collection.
-#[derive(Default, Serialize, Deserialize)]
+///
+/// It is (de)serialized as a versioned [`CollectionFile`], see
+/// [`Collection::load`] and [`Collection::save`].
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(into = "CollectionFile", try_from = "CollectionFile")]
 pub struct Collection {
     pub skus: BTreeMap<SKU, u32>,
     pub singles: BTreeMap<Item, u32>,
 }
 
+/// The current version of the [`CollectionFile`] format.
+pub const COLLECTION_VERSION: u32 = 1;
+
+/// The on-disk format of a [`Collection`], as json or toml.
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "skus": { "swz25": 2 },
+///   "singles": [{ "type": "pilot", "xws": "poedameron", "count": 1 }]
+/// }
+/// ```
+///
+/// The singles are a list instead of a map since json and toml keys can only
+/// be strings.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CollectionFile {
+    pub version: u32,
+    #[serde(default)]
+    pub skus: BTreeMap<SKU, u32>,
+    #[serde(default)]
+    pub singles: Vec<SingleCount>,
+}
+
+/// A single item and its count in a [`CollectionFile`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SingleCount {
+    pub r#type: ItemType,
+    pub xws: String,
+    pub count: u32,
+}
+
+impl From<Collection> for CollectionFile {
+    fn from(c: Collection) -> Self {
+        CollectionFile {
+            version: COLLECTION_VERSION,
+            skus: c.skus,
+            singles: c
+                .singles
+                .into_iter()
+                .map(|(item, count)| SingleCount {
+                    r#type: item.r#type,
+                    xws: item.xws,
+                    count,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<CollectionFile> for Collection {
+    type Error = String;
+
+    fn try_from(f: CollectionFile) -> Result<Self, Self::Error> {
+        if f.version > COLLECTION_VERSION {
+            return Err(format!(
+                "collection version {} is newer than supported version {}",
+                f.version, COLLECTION_VERSION
+            ));
+        }
+
+        let mut collection = Collection {
+            skus: f.skus,
+            ..Default::default()
+        };
+        for s in f.singles {
+            collection.add_single(
+                Item {
+                    r#type: s.r#type,
+                    xws: s.xws,
+                },
+                s.count,
+            );
+        }
+        Ok(collection)
+    }
+}
+
 /// An Inventory is a just a count of Items, where Items have just enough
 /// information to look them up in xwing-data2 or an catalog of expansion
 /// contents.
@@ -49,9 +284,18 @@ pub type Inventory = BTreeMap<Item, u32>;
 impl Collection {
     /// Produce a count of all items in expansions and add them to the singles.
     ///
+    /// Legacy ship models in the singles are counted as the ships they can be
+    /// used as, see [`compatibility`]. Not every product has both models and
+    /// cards, e.g. conversion kits, so see [`gaps`] for the ships that can't
+    /// be fielded.
+    ///
     /// Returns a list of expansions that weren't found in the catalog.
-    pub fn inventory(&self, catalog: &expansions::Catalog) -> (Inventory, Vec<String>) {
-        let mut inventory = self.singles.clone();
+    pub fn inventory(
+        &self,
+        catalog: &expansions::Catalog,
+        compatibility: &compatibility::Compatibility,
+    ) -> (Inventory, Vec<String>) {
+        let mut inventory = compatibility.apply(self.singles.clone());
         let mut missing_expansions = vec![];
 
         for (sku, c) in &self.skus {
@@ -68,33 +312,153 @@ impl Collection {
         }
         (inventory, missing_expansions)
     }
+
+    /// Loads a collection saved by [`Collection::save`]. Files ending in
+    /// `.toml` are read as toml, everything else as json.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        from_json_or_toml(path)
+    }
+
+    /// Saves the collection as toml if `path` ends in `.toml`, otherwise as
+    /// json.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let buffer = if is_toml(path) {
+            toml::to_string(self).map_err(Error::TomlSerialize)?
+        } else {
+            serde_json::to_string_pretty(self).map_err(Error::Json)?
+        };
+        fs::write(path, buffer).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    pub fn add_expansion(&mut self, sku: &str, count: u32) {
+        *self.skus.entry(sku.to_owned()).or_insert(0) += count;
+    }
+
+    pub fn add_single(&mut self, item: Item, count: u32) {
+        *self.singles.entry(item).or_insert(0) += count;
+    }
+
+    /// Adds everything in `other` to this collection, e.g. to pool two
+    /// collections for a team event.
+    pub fn merge(&mut self, other: &Collection) {
+        for (sku, count) in &other.skus {
+            self.add_expansion(sku, *count);
+        }
+        for (item, count) in &other.singles {
+            self.add_single(item.clone(), *count);
+        }
+    }
+
+    /// Removes everything in `other` from this collection, e.g. to split a
+    /// pooled collection up again. Anything not in this collection is ignored.
+    pub fn subtract(&mut self, other: &Collection) {
+        for (sku, count) in &other.skus {
+            self.remove_expansion(sku, *count);
+        }
+        for (item, count) in &other.singles {
+            self.remove_single(item, *count);
+        }
+    }
+
+    /// Removes up to `count` of the expansion, dropping it from the collection
+    /// if there are none left. Returns the number actually removed.
+    pub fn remove_expansion(&mut self, sku: &str, count: u32) -> u32 {
+        remove_count(&mut self.skus, sku, count)
+    }
+
+    /// Removes up to `count` of the item, dropping it from the collection if
+    /// there are none left. Returns the number actually removed.
+    pub fn remove_single(&mut self, item: &Item, count: u32) -> u32 {
+        remove_count(&mut self.singles, item, count)
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("toml"))
+}
+
+fn remove_count<K, Q>(counts: &mut BTreeMap<K, u32>, key: &Q, count: u32) -> u32
+where
+    K: std::borrow::Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    match counts.get_mut(key) {
+        None => 0,
+        Some(c) if *c <= count => {
+            let removed = *c;
+            counts.remove(key);
+            removed
+        }
+        Some(c) => {
+            *c -= count;
+            count
+        }
+    }
 }
 
 /// This is the full ship as defined by the expansions.
 ///
-/// TODO: Add a "miniature/chassis" type compatibility that reflects usability
-/// per tournament regulations.
+/// Legacy models are counted as the ships they can be used as, see
+/// [`compatibility`].
 #[derive(Serialize, Debug)]
 pub struct ShipRecord {
     pub name: String,
     pub xws: String,
+    pub size: String,
     pub factions: String,
+    /// The attacks with their arcs, e.g. `Front Arc 3`.
+    pub attack: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agility: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hull: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shields: Option<u32>,
+    pub actions: String,
+    /// The maneuver codes, e.g. `1TW 1BW 1FW`, see [`Records::clear_dials`].
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub dial: String,
+    pub dial_codes: String,
 
     pub count: u32,
 
     // just a long string of the sources for informational purposes
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<String>,
+
+    /// As `name: text`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ship_ability: Option<String>,
 }
 
 impl ShipRecord {
     /// Turns skus and xws id's into display names.
-    pub fn build(xws: &str, count: u32, data: &Data, catalog: &Catalog) -> Result<Self, ErrorKind> {
+    pub fn build(xws: &str, count: u32, data: &Data, catalog: &Catalog) -> Result<Self, Error> {
         match data.get_ship_model(xws) {
-            None => Err(ErrorKind::NotFound),
+            None => Err(Error::UnknownXws(Item {
+                r#type: ItemType::Ship,
+                xws: xws.to_owned(),
+            })),
             Some(s) => Ok(Self {
+                attack: s.attacks(),
+                agility: s.stat("agility"),
+                hull: s.stat("hull"),
+                shields: s.stat("shields"),
+                actions: s
+                    .actions
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                dial: s.dial.join(" "),
+                dial_codes: s.dial_codes.join(","),
                 name: s.name,
                 xws: s.xws,
+                size: s.size,
                 factions: s.faction,
                 sources: catalog
                     .sources
@@ -104,6 +468,9 @@ impl ShipRecord {
                     })
                     .map(|s| format_sources(catalog, s)),
                 count,
+                ship_ability: data
+                    .get_ship_ability(xws)
+                    .map(|a| format!("{}: {}", a.name, a.text)),
             }),
         }
     }
@@ -117,25 +484,44 @@ pub struct PilotRecord {
     pub ship: String,
     pub xws: String,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
     pub initiative: u32,
+    /// The number of copies allowed in a list, 0 if there is no limit.
+    pub limited: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loadout: Option<u32>,
+    pub standard_loadout: bool,
+    pub slots: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charges: Option<Charges>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<Force>,
+    pub keywords: String,
 
     pub count: u32,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ability: Option<String>,
+    /// As `name: text`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ship_ability: Option<String>,
 }
 
 impl PilotRecord {
     /// Turns skus and xws id's into display names.
-    pub fn build(
-        xws: &str,
-        count: u32,
-        data: &Data,
-        expansions: &Catalog,
-    ) -> Result<Self, ErrorKind> {
+    pub fn build(xws: &str, count: u32, data: &Data, expansions: &Catalog) -> Result<Self, Error> {
         // TODO: there must be a better way to do the restrictions
         match data.get_pilot(xws) {
-            None => Err(ErrorKind::NotFound),
+            None => Err(Error::UnknownXws(Item {
+                r#type: ItemType::Pilot,
+                xws: xws.to_owned(),
+            })),
             Some((s, p)) => Ok(Self {
                 faction: data
                     .get_faction(s.faction.as_str())
@@ -143,7 +529,21 @@ impl PilotRecord {
                 ship: s.name.to_owned(),
                 name: p.name.to_owned(),
                 xws: p.xws.to_owned(),
+                caption: p.caption.to_owned(),
                 initiative: p.initiative,
+                limited: p.limited,
+                cost: p.cost,
+                loadout: p.loadout,
+                standard_loadout: p.standard_loadout.as_ref().is_some_and(|l| !l.is_empty()),
+                slots: p
+                    .slots
+                    .iter()
+                    .map(|k| format!("{:?}", k))
+                    .collect::<Vec<String>>()
+                    .join(","),
+                charges: p.charges.to_owned(),
+                force: p.force.to_owned(),
+                keywords: p.keywords.join(","),
                 count,
                 sources: expansions
                     .sources
@@ -152,6 +552,11 @@ impl PilotRecord {
                         xws: xws.to_owned(),
                     })
                     .map(|s| format_sources(expansions, s)),
+                ability: p.ability.to_owned(),
+                ship_ability: p
+                    .ship_ability
+                    .as_ref()
+                    .map(|a| format!("{}: {}", a.name, a.text)),
             }),
         }
     }
@@ -163,12 +568,23 @@ pub struct UpgradeRecord {
     pub xws: String,
     pub r#type: String,
     pub slots: String,
+    /// The types and slots of every side, so cards that take more than one
+    /// slot, or have a different type on the back, can be found by any of
+    /// them.
+    pub all_slot_types: String,
     pub name: String,
+    /// Which side of the card this is, only for the records with one per
+    /// side.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<usize>,
     pub faction_restriction: String,
     pub size_restriction: String,
     pub ship_restriction: String,
     pub arc_restriction: String,
     pub keyword_restriction: String,
+    /// All the restrictions, including the equipped upgrades and actions,
+    /// e.g. `(Rebel Alliance or Resistance) and Small ship`.
+    pub restrictions: String,
 
     pub count: u32,
     pub force_side_restriction: String,
@@ -176,36 +592,82 @@ pub struct UpgradeRecord {
     // just a long string of the sources for informational purposes
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charges: Option<Charges>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ability: Option<String>,
 }
 
 impl UpgradeRecord {
-    /// Turns skus and xws id's into display names.
-    pub fn build(xws: &str, count: u32, data: &Data, catalog: &Catalog) -> Result<Self, ErrorKind> {
-        // TODO: there must be a better way to do the restrictions
+    /// Turns skus and xws id's into display names, using the front side of
+    /// the card for the name, type, slots, charges and ability.
+    pub fn build(xws: &str, count: u32, data: &Data, catalog: &Catalog) -> Result<Self, Error> {
+        Self::build_side(xws, count, None, data, catalog)
+    }
+
+    /// A record for every side of the card, named after the side. Only the
+    /// first one has the `count`, so the card isn't counted once per side.
+    pub fn build_sides(
+        xws: &str,
+        count: u32,
+        data: &Data,
+        catalog: &Catalog,
+    ) -> Result<Vec<Self>, Error> {
+        let sides = data.get_upgrade(xws).map_or(1, |u| u.sides.len().max(1));
+        (0..sides)
+            .map(|i| {
+                let count = if i == 0 { count } else { 0 };
+                Self::build_side(xws, count, Some(i), data, catalog)
+            })
+            .collect()
+    }
+
+    fn build_side(
+        xws: &str,
+        count: u32,
+        side: Option<usize>,
+        data: &Data,
+        catalog: &Catalog,
+    ) -> Result<Self, Error> {
+        let i = side.unwrap_or(0);
+        let format_slots = |slots: &[SlotKind]| {
+            slots
+                .iter()
+                .map(|k| format!("{:?}", k))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
         match data.get_upgrade(xws) {
-            None => Err(ErrorKind::NotFound),
+            None => Err(Error::UnknownXws(Item {
+                r#type: ItemType::Upgrade,
+                xws: xws.to_owned(),
+            })),
             Some(u) => Ok(Self {
-                name: u.name.to_owned(),
+                name: side
+                    .and_then(|i| u.sides.get(i))
+                    .and_then(|s| s.title.to_owned())
+                    .unwrap_or(u.name.to_owned()),
                 xws: u.xws.to_owned(),
+                side,
                 count,
                 r#type: u
                     .sides
-                    .first()
+                    .get(i)
                     .map(|s| format!("{:?}", s.r#type)) //FIXME
-                    .unwrap_or("unknown".to_owned())
-                    .to_owned(),
+                    .unwrap_or("unknown".to_owned()),
                 slots: u
                     .sides
-                    .first()
-                    .map(|s| {
-                        s.slots
-                            .iter()
-                            .map(|k| format!("{:?}", k).to_owned())
-                            .collect::<Vec<String>>()
-                            .join(",")
-                    })
-                    .unwrap_or("unknown".to_owned())
-                    .to_owned(),
+                    .get(i)
+                    .map(|s| format_slots(&s.slots))
+                    .unwrap_or("unknown".to_owned()),
+                all_slot_types: format_slots(&u.slot_types()),
+                charges: u.sides.get(i).and_then(|s| s.charges.to_owned()),
+                ability: u
+                    .sides
+                    .get(i)
+                    .and_then(|s| s.ability.to_owned().or(s.text.to_owned())),
                 faction_restriction: format_restriction(
                     data,
                     &u.restrictions,
@@ -224,6 +686,7 @@ impl UpgradeRecord {
                     Restriction::ForceSide,
                 ),
                 arc_restriction: format_restriction(data, &u.restrictions, Restriction::Arcs),
+                restrictions: u.restriction_tree().render(data),
                 sources: catalog
                     .sources
                     .get(&Item {
@@ -236,6 +699,126 @@ impl UpgradeRecord {
     }
 }
 
+/// An obstacle, like an asteroid or debris cloud.
+#[derive(Serialize, Debug)]
+pub struct ObstacleRecord {
+    pub xws: String,
+    pub name: String,
+    pub r#type: String,
+
+    pub count: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<String>,
+}
+
+impl ObstacleRecord {
+    /// Turns skus and xws id's into display names.
+    pub fn build(xws: &str, count: u32, data: &Data, catalog: &Catalog) -> Result<Self, Error> {
+        let item = Item {
+            r#type: ItemType::Obstacle,
+            xws: xws.to_owned(),
+        };
+        match data.get_obstacle(xws) {
+            None => Err(Error::UnknownXws(item)),
+            Some(o) => Ok(Self {
+                xws: o.xws.to_owned(),
+                name: o.name.to_owned(),
+                r#type: o.r#type.to_owned().unwrap_or_default(),
+                count,
+                sources: catalog
+                    .sources
+                    .get(&item)
+                    .map(|s| format_sources(catalog, s)),
+            }),
+        }
+    }
+}
+
+/// A single damage card and the deck it is from, since the cards are what
+/// the expansions list and it's easy to lose a few.
+#[derive(Serialize, Debug)]
+pub struct DamageDeckRecord {
+    pub deck: String,
+    pub xws: String,
+    pub name: String,
+    pub r#type: String,
+    /// The number of copies in a full deck.
+    pub amount: u32,
+
+    pub count: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<String>,
+}
+
+impl DamageDeckRecord {
+    /// Turns skus and xws id's into display names.
+    pub fn build(xws: &str, count: u32, data: &Data, catalog: &Catalog) -> Result<Self, Error> {
+        let item = Item {
+            r#type: ItemType::Damage,
+            xws: xws.to_owned(),
+        };
+        match data.get_damage_card(xws) {
+            None => Err(Error::UnknownXws(item)),
+            Some((d, c)) => Ok(Self {
+                deck: d.name.to_owned(),
+                xws: c.xws.to_owned(),
+                name: c.title.to_owned(),
+                r#type: c.r#type.to_owned(),
+                amount: c.amount,
+                count,
+                sources: catalog
+                    .sources
+                    .get(&item)
+                    .map(|s| format_sources(catalog, s)),
+            }),
+        }
+    }
+}
+
+/// A physical component, like a dial or a range ruler.
+#[derive(Serialize, Debug)]
+pub struct ComponentRecord {
+    pub r#type: ItemType,
+    pub xws: String,
+    pub name: String,
+
+    pub count: u32,
+    /// The ship models in the inventory that use the component, see
+    /// [`components::ships`].
+    pub ships: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<String>,
+}
+
+impl ComponentRecord {
+    /// Turns skus and xws id's into display names.
+    pub fn build(
+        item: &Item,
+        count: u32,
+        inventory: &Inventory,
+        data: &Data,
+        catalog: &Catalog,
+    ) -> Result<Self, Error> {
+        match components::name(item, data) {
+            None => Err(Error::UnknownXws(item.clone())),
+            Some(name) => Ok(Self {
+                r#type: item.r#type,
+                xws: item.xws.to_owned(),
+                name,
+                count,
+                ships: components::ships(item, inventory),
+                sources: catalog
+                    .sources
+                    .get(item)
+                    .map(|s| format_sources(catalog, s)),
+            }),
+        }
+    }
+}
+
 fn format_restriction(
     data: &xwingdata2::Data,
     restrictions: &Vec<xwingdata2::Restrictions>,
@@ -287,61 +870,185 @@ pub struct Records {
     pub ships: Vec<ShipRecord>,
     pub pilots: Vec<PilotRecord>,
     pub upgrades: Vec<UpgradeRecord>,
+    pub obstacles: Vec<ObstacleRecord>,
+    pub damage_decks: Vec<DamageDeckRecord>,
+    pub components: Vec<ComponentRecord>,
 }
 
 impl Records {
-    pub fn build(inventory: &Inventory, data: &Data, catalog: &Catalog) -> Records {
+    /// Leaves the dials out of the ship records, since they are only wanted
+    /// with `--dials`.
+    pub fn clear_dials(&mut self) {
+        for s in self.ships.iter_mut() {
+            s.dial.clear();
+        }
+    }
+
+    /// Replaces the upgrades with a record per side of each card, for
+    /// double-sided cards like configurations.
+    pub fn split_upgrade_sides(&mut self, data: &Data, catalog: &Catalog) {
+        self.upgrades = self
+            .upgrades
+            .iter()
+            .flat_map(|u| {
+                UpgradeRecord::build_sides(&u.xws, u.count, data, catalog).unwrap_or_default()
+            })
+            .collect();
+    }
+
+    /// Builds a record for every item in the inventory.
+    ///
+    /// Returns the errors for any items that couldn't be found instead of
+    /// stopping at the first one, since collections tend to have a few.
+    pub fn build(inventory: &Inventory, data: &Data, catalog: &Catalog) -> (Records, Vec<Error>) {
         let mut records = Records::default();
+        let mut errors = vec![];
 
         for (item, count) in inventory {
             match &item.r#type {
                 ItemType::Ship => {
                     match ShipRecord::build(&item.xws, *count, data, catalog) {
                         Ok(r) => records.ships.push(r),
-                        Err(_) => println!("ship not found: {}", &item.xws),
+                        Err(e) => errors.push(e),
                     };
                 }
                 ItemType::Pilot => {
                     match PilotRecord::build(&item.xws, *count, data, catalog) {
                         Ok(r) => records.pilots.push(r),
-                        Err(_) => println!("pilot not found: {}", &item.xws),
+                        Err(e) => errors.push(e),
                     };
                 }
                 ItemType::Upgrade => {
                     match UpgradeRecord::build(&item.xws, *count, data, catalog) {
                         Ok(u) => records.upgrades.push(u),
-                        Err(_) => println!("Upgrade not found: {}", &item.xws),
+                        Err(e) => errors.push(e),
+                    };
+                }
+                ItemType::Obstacle => {
+                    match ObstacleRecord::build(&item.xws, *count, data, catalog) {
+                        Ok(o) => records.obstacles.push(o),
+                        Err(e) => errors.push(e),
                     };
                 }
-                _ => (),
+                ItemType::Damage => {
+                    match DamageDeckRecord::build(&item.xws, *count, data, catalog) {
+                        Ok(d) => records.damage_decks.push(d),
+                        Err(e) => errors.push(e),
+                    };
+                }
+                // built below, since missing dials are included
+                ItemType::Dial | ItemType::Template => (),
+            };
+        }
+        for item in components::items(inventory) {
+            let count = *inventory.get(&item).unwrap_or(&0);
+            match ComponentRecord::build(&item, count, inventory, data, catalog) {
+                Ok(c) => records.components.push(c),
+                Err(e) => errors.push(e),
             };
         }
-        records
+        (records, errors)
     }
 }
 
-pub fn generate_xls(
+/// The optional parts of the generated spreadsheet.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct SheetOptions {
+    /// Leave out the unowned expansions and their contents.
+    pub only_owned: bool,
+    /// Add a `Dial` column with the maneuvers to the `Ships` sheet.
+    pub dials: bool,
+    /// Add a row per side of double-sided cards to the `Upgrades` sheet.
+    pub upgrade_sides: bool,
+    /// Add an `Availability` sheet with the owned upgrades that fit each
+    /// owned pilot's slots.
+    pub availability: bool,
+}
+
+/// Saves the inventory spreadsheet to `path`, e.g. `XWingTMG2_Inventory.xlsx`.
+pub fn generate_xls<P: AsRef<Path>>(
     catalog: &Catalog,
     data: &Data,
     collection: &Collection,
     inventory: &Inventory,
-    only_owned: bool,
-) -> Result<(), XlsxError> {
+    options: &SheetOptions,
+    path: P,
+) -> Result<(), Error> {
+    let mut workbook = build_workbook(catalog, data, collection, inventory, options)?;
+    workbook.save(path)?;
+
+    Ok(())
+}
+
+/// Writes the inventory spreadsheet to any seekable sink, like a `Cursor`.
+pub fn generate_xls_to_writer<W: Write + Seek + Send>(
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+    options: &SheetOptions,
+    writer: W,
+) -> Result<(), Error> {
+    let mut workbook = build_workbook(catalog, data, collection, inventory, options)?;
+    workbook.save_to_writer(writer)?;
+
+    Ok(())
+}
+
+/// Returns the inventory spreadsheet as the bytes of an `.xlsx` file.
+pub fn generate_xls_to_buffer(
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+    options: &SheetOptions,
+) -> Result<Vec<u8>, Error> {
+    let mut workbook = build_workbook(catalog, data, collection, inventory, options)?;
+    Ok(workbook.save_to_buffer()?)
+}
+
+fn build_workbook(
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+    options: &SheetOptions,
+) -> Result<Workbook, Error> {
     let mut workbook = Workbook::new();
 
-    add_expansion_sheet(&mut workbook, catalog, collection, only_owned)?;
+    add_expansion_sheet(&mut workbook, catalog, collection, options.only_owned)?;
     // This must be done seperately because of the way borrows work on the
     // workbook make it hard to work with more than 1 sheet at once.
-    add_ships_sheet(&mut workbook, catalog, data, collection, inventory)?;
+    add_ships_sheet(
+        &mut workbook,
+        catalog,
+        data,
+        collection,
+        inventory,
+        options.dials,
+    )?;
     add_pilots_sheet(&mut workbook, catalog, data, collection, inventory)?;
-    add_upgrades_sheet(&mut workbook, catalog, data, collection, inventory)?;
-
-    workbook.save("XWingTMG2_Inventory.xlsx")?;
+    add_upgrades_sheet(
+        &mut workbook,
+        catalog,
+        data,
+        collection,
+        inventory,
+        options.upgrade_sides,
+    )?;
+    add_obstacles_sheet(&mut workbook, catalog, data, collection, inventory)?;
+    add_damage_decks_sheet(&mut workbook, catalog, data, collection, inventory)?;
+    add_components_sheet(&mut workbook, catalog, data, collection, inventory)?;
+    add_gaps_sheet(&mut workbook, data, inventory)?;
+    add_value_sheet(&mut workbook, catalog, data, collection, inventory)?;
+    if options.availability {
+        add_availability_sheet(&mut workbook, data, inventory)?;
+    }
 
-    Ok(())
+    Ok(workbook)
 }
 
-const EXPANSION_COLS: [&str; 4] = ["Owned", "Name", "Wave", "SKU"];
+const EXPANSION_COLS: [&str; 5] = ["Owned", "Name", "Wave", "SKU", "Kind"];
 
 fn add_expansion_sheet(
     workbook: &mut Workbook,
@@ -369,6 +1076,7 @@ fn add_expansion_sheet(
         worksheet.write(row, 1, &exp.name)?;
         worksheet.write(row, 2, exp.wave)?;
         worksheet.write(row, 3, &exp.sku)?;
+        worksheet.write(row, 4, exp.kind.as_ref())?;
         row += 1;
     }
     let mut table = Table::new();
@@ -393,12 +1101,15 @@ fn total_func(item: &Item, singles_cell: String, catalog: &Catalog) -> String {
     func
 }
 
+/// Adds the `Ships` sheet, with a `Dial` column of the maneuvers if `dials`
+/// is set, to match loose dials to their ships.
 fn add_ships_sheet(
     workbook: &mut Workbook,
     catalog: &Catalog,
     data: &Data,
     collection: &Collection,
     inventory: &BTreeMap<Item, u32>,
+    dials: bool,
 ) -> Result<(), XlsxError> {
     let ships = workbook.add_worksheet().set_name("Ships")?;
 
@@ -406,15 +1117,15 @@ fn add_ships_sheet(
     let ship_singles_col = 2;
     for item in inventory.keys() {
         if item.r#type == ItemType::Ship {
-            let model = match data.get_ship_model(&item.xws) {
-                Some(m) => m,
-                None => {
-                    println!("xslx: missing ship {}", item.xws);
+            let record = match ShipRecord::build(&item.xws, 1, data, catalog) {
+                Ok(r) => r,
+                Err(_) => {
+                    eprintln!("xslx: missing ship {}", item.xws);
                     continue;
                 }
             };
 
-            ships.write(ship_row, 0, &model.name)?;
+            ships.write(ship_row, 0, &record.name)?;
             ships.write_dynamic_formula(
                 ship_row,
                 1,
@@ -425,23 +1136,27 @@ fn add_ships_sheet(
                 2,
                 *collection.singles.get(item).unwrap_or(&0) as i32,
             )?;
-            ships.write(ship_row, 3, &model.size)?;
-            ships.write(ship_row, 4, &model.faction)?;
-            ships.write(ship_row, 5, &item.xws)?;
-            ships.write(
-                ship_row,
-                6,
-                catalog
-                    .sources
-                    .get(item)
-                    .map(|s| format_sources(catalog, s))
-                    .unwrap_or("".to_string()),
-            )?;
+            ships.write(ship_row, 3, &record.size)?;
+            ships.write(ship_row, 4, &record.factions)?;
+            ships.write(ship_row, 5, &record.attack)?;
+            for (col, stat) in [(6, record.agility), (7, record.hull), (8, record.shields)] {
+                if let Some(v) = stat {
+                    ships.write(ship_row, col, v)?;
+                }
+            }
+            ships.write(ship_row, 9, &record.actions)?;
+            ships.write(ship_row, 10, &record.dial_codes)?;
+            ships.write(ship_row, 11, &item.xws)?;
+            ships.write(ship_row, 12, record.sources.as_deref().unwrap_or(""))?;
+            ships.write(ship_row, 13, record.ship_ability.as_deref().unwrap_or(""))?;
+            if dials {
+                ships.write(ship_row, 14, &record.dial)?;
+            }
 
             ship_row += 1;
         }
     }
-    let columns = vec![
+    let mut columns = vec![
         TableColumn::new()
             .set_header("Name")
             .set_total_label("Totals"),
@@ -453,11 +1168,21 @@ fn add_ships_sheet(
             .set_total_function(TableFunction::Sum),
         TableColumn::new().set_header("Size"),
         TableColumn::new().set_header("Factions"),
+        TableColumn::new().set_header("Attack"),
+        TableColumn::new().set_header("Agility"),
+        TableColumn::new().set_header("Hull"),
+        TableColumn::new().set_header("Shields"),
+        TableColumn::new().set_header("Actions"),
+        TableColumn::new().set_header("Dial Codes"),
         TableColumn::new()
             .set_header("XWS")
             .set_total_function(TableFunction::Count),
         TableColumn::new().set_header("Sources"),
+        TableColumn::new().set_header("Ship Ability"),
     ];
+    if dials {
+        columns.push(TableColumn::new().set_header("Dial"));
+    }
     let mut table = Table::new();
     let table = table
         .set_name("ShipTable")
@@ -482,22 +1207,17 @@ fn add_pilots_sheet(
     let pilot_singles_col = 4;
     for item in inventory.keys() {
         if item.r#type == ItemType::Pilot {
-            // TODO: probably don't need to
-            let (ship, pilot) = match data.get_pilot(&item.xws) {
-                Some(m) => m,
-                None => {
-                    println!("xslx: missing pilot {}", item.xws);
+            let record = match PilotRecord::build(&item.xws, 1, data, catalog) {
+                Ok(r) => r,
+                Err(_) => {
+                    eprintln!("xslx: missing pilot {}", item.xws);
                     continue;
                 }
             };
 
-            pilots.write(pilot_row, 0, &pilot.name)?;
-            pilots.write(pilot_row, 1, &ship.name)?;
-            pilots.write(
-                pilot_row,
-                2,
-                pilot.caption.as_ref().map_or_else(|| "", |c| c.as_str()),
-            )?;
+            pilots.write(pilot_row, 0, &record.name)?;
+            pilots.write(pilot_row, 1, &record.ship)?;
+            pilots.write(pilot_row, 2, record.caption.as_deref().unwrap_or(""))?;
 
             pilots.write_dynamic_formula(
                 pilot_row,
@@ -510,32 +1230,29 @@ fn add_pilots_sheet(
                 *collection.singles.get(item).unwrap_or(&0) as i32,
             )?;
 
-            pilots.write(
-                pilot_row,
-                5,
-                data.get_faction(ship.faction.as_str())
-                    .map_or(ship.faction.to_owned(), |f| f.name.to_owned()),
-            )?;
-            pilots.write(pilot_row, 6, pilot.initiative)?;
-            pilots.write(
-                pilot_row,
-                7,
-                pilot
-                    .standard_loadout
-                    .as_ref()
-                    .map_or_else(|| false, |v| !v.is_empty()),
-            )?;
+            pilots.write(pilot_row, 5, &record.faction)?;
+            pilots.write(pilot_row, 6, record.initiative)?;
+            pilots.write(pilot_row, 7, record.limited)?;
+            if let Some(cost) = record.cost {
+                pilots.write(pilot_row, 8, cost)?;
+            }
+            if let Some(loadout) = record.loadout {
+                pilots.write(pilot_row, 9, loadout)?;
+            }
+            pilots.write(pilot_row, 10, record.standard_loadout)?;
+            pilots.write(pilot_row, 11, &record.slots)?;
+            if let Some(charges) = &record.charges {
+                pilots.write(pilot_row, 12, charges.value)?;
+            }
+            if let Some(force) = &record.force {
+                pilots.write(pilot_row, 13, force.value)?;
+            }
+            pilots.write(pilot_row, 14, &record.keywords)?;
 
-            pilots.write(pilot_row, 8, &pilot.xws)?;
-            pilots.write(
-                pilot_row,
-                9,
-                catalog
-                    .sources
-                    .get(item)
-                    .map(|s| format_sources(catalog, s))
-                    .unwrap_or("".to_string()),
-            )?;
+            pilots.write(pilot_row, 15, &record.xws)?;
+            pilots.write(pilot_row, 16, record.sources.as_deref().unwrap_or(""))?;
+            pilots.write(pilot_row, 17, record.ability.as_deref().unwrap_or(""))?;
+            pilots.write(pilot_row, 18, record.ship_ability.as_deref().unwrap_or(""))?;
 
             pilot_row += 1;
         }
@@ -554,11 +1271,20 @@ fn add_pilots_sheet(
             .set_total_function(TableFunction::Sum),
         TableColumn::new().set_header("Faction"),
         TableColumn::new().set_header("Initiative"),
+        TableColumn::new().set_header("Limited"),
+        TableColumn::new().set_header("Cost"),
+        TableColumn::new().set_header("Loadout"),
         TableColumn::new().set_header("Standard Loadout"),
+        TableColumn::new().set_header("Slots"),
+        TableColumn::new().set_header("Charges"),
+        TableColumn::new().set_header("Force"),
+        TableColumn::new().set_header("Keywords"),
         TableColumn::new()
             .set_header("XWS")
             .set_total_function(TableFunction::Count),
         TableColumn::new().set_header("Sources"),
+        TableColumn::new().set_header("Ability"),
+        TableColumn::new().set_header("Ship Ability"),
     ];
     let mut table = Table::new();
     let table = table
@@ -571,12 +1297,15 @@ fn add_pilots_sheet(
     Ok(())
 }
 
+/// Adds the `Upgrades` sheet, with a row per side of double-sided cards if
+/// `sides` is set. Only the first row of a card has its `Singles`.
 fn add_upgrades_sheet(
     workbook: &mut Workbook,
     catalog: &Catalog,
     data: &Data,
     collection: &Collection,
     inventory: &BTreeMap<Item, u32>,
+    sides: bool,
 ) -> Result<(), XlsxError> {
     let upgrades = workbook.add_worksheet().set_name("Upgrades")?;
 
@@ -584,55 +1313,64 @@ fn add_upgrades_sheet(
     let upgrade_singles_col = 3;
     for item in inventory.keys() {
         if item.r#type == ItemType::Upgrade {
-            let upgrade = match data.get_upgrade(&item.xws) {
-                Some(m) => m,
-                None => {
-                    println!("xslx: missing upgrade {}", item.xws);
+            let records = match sides {
+                true => UpgradeRecord::build_sides(&item.xws, 1, data, catalog),
+                false => UpgradeRecord::build(&item.xws, 1, data, catalog).map(|r| vec![r]),
+            };
+            let records = match records {
+                Ok(r) => r,
+                Err(_) => {
+                    eprintln!("xslx: missing upgrade {}", item.xws);
                     continue;
                 }
             };
 
-            let record = UpgradeRecord::build(&item.xws, 1, data, catalog).unwrap();
+            // the card is only counted on its first row, but singles typed
+            // into any of its rows are added up
+            let singles = match records.len() {
+                1 => row_col_to_cell(upgrade_row, upgrade_singles_col),
+                n => format!(
+                    "SUM({}:{})",
+                    row_col_to_cell(upgrade_row, upgrade_singles_col),
+                    row_col_to_cell(upgrade_row + n as u32 - 1, upgrade_singles_col)
+                ),
+            };
+            for (i, record) in records.iter().enumerate() {
+                upgrades.write(upgrade_row, 0, &record.name)?;
+                upgrades.write(upgrade_row, 1, &record.r#type)?;
 
-            upgrades.write(upgrade_row, 0, &upgrade.name)?;
-            upgrades.write(upgrade_row, 1, &record.r#type)?;
+                if i == 0 {
+                    upgrades.write_dynamic_formula(
+                        upgrade_row,
+                        2,
+                        total_func(item, singles.to_owned(), catalog).as_str(),
+                    )?;
+                    upgrades.write(
+                        upgrade_row,
+                        upgrade_singles_col,
+                        *collection.singles.get(item).unwrap_or(&0) as i32,
+                    )?;
+                }
 
-            upgrades.write_dynamic_formula(
-                upgrade_row,
-                2,
-                total_func(
-                    item,
-                    row_col_to_cell(upgrade_row, upgrade_singles_col),
-                    catalog,
-                )
-                .as_str(),
-            )?;
-            upgrades.write(
-                upgrade_row,
-                upgrade_singles_col,
-                *collection.singles.get(item).unwrap_or(&0) as i32,
-            )?;
+                upgrades.write(upgrade_row, 4, &record.faction_restriction)?;
+                upgrades.write(upgrade_row, 5, &record.slots)?;
+                upgrades.write(upgrade_row, 6, &record.all_slot_types)?;
+                upgrades.write(upgrade_row, 7, &record.ship_restriction)?;
+                upgrades.write(upgrade_row, 8, &record.size_restriction)?;
+                upgrades.write(upgrade_row, 9, &record.arc_restriction)?;
+                upgrades.write(upgrade_row, 10, &record.force_side_restriction)?;
+                upgrades.write(upgrade_row, 11, &record.keyword_restriction)?;
+                upgrades.write(upgrade_row, 12, &record.restrictions)?;
 
-            upgrades.write(upgrade_row, 4, &record.faction_restriction)?;
-            upgrades.write(upgrade_row, 5, &record.slots)?;
-            upgrades.write(upgrade_row, 6, &record.ship_restriction)?;
-            upgrades.write(upgrade_row, 7, &record.size_restriction)?;
-            upgrades.write(upgrade_row, 8, &record.arc_restriction)?;
-            upgrades.write(upgrade_row, 9, &record.force_side_restriction)?;
-            upgrades.write(upgrade_row, 10, &record.keyword_restriction)?;
-
-            upgrades.write(upgrade_row, 11, &upgrade.xws)?;
-            upgrades.write(
-                upgrade_row,
-                12,
-                catalog
-                    .sources
-                    .get(item)
-                    .map(|s| format_sources(catalog, s))
-                    .unwrap_or("".to_string()), //.unwrap_or("".to_string()),
-            )?;
+                upgrades.write(upgrade_row, 13, &record.xws)?;
+                upgrades.write(upgrade_row, 14, record.sources.as_deref().unwrap_or(""))?;
+                if let Some(charges) = &record.charges {
+                    upgrades.write(upgrade_row, 15, charges.value)?;
+                }
+                upgrades.write(upgrade_row, 16, record.ability.as_deref().unwrap_or(""))?;
 
-            upgrade_row += 1;
+                upgrade_row += 1;
+            }
         }
     }
     let mut table = Table::new();
@@ -652,15 +1390,19 @@ fn add_upgrades_sheet(
             .set_total_function(TableFunction::Sum),
         TableColumn::new().set_header("Faction Restriction"),
         TableColumn::new().set_header("Slots"),
+        TableColumn::new().set_header("All Slot Types"),
         TableColumn::new().set_header("Ship Restriction"),
         TableColumn::new().set_header("Size Restriction"),
         TableColumn::new().set_header("Arc Restriction"),
         TableColumn::new().set_header("Force Side Restriction"),
         TableColumn::new().set_header("Keyword Restriction"),
+        TableColumn::new().set_header("Restrictions"),
         TableColumn::new()
             .set_header("XWS")
             .set_total_function(TableFunction::Count),
         TableColumn::new().set_header("Sources"),
+        TableColumn::new().set_header("Charges"),
+        TableColumn::new().set_header("Ability"),
     ];
     table.set_columns(&columns);
 
@@ -668,3 +1410,477 @@ fn add_upgrades_sheet(
     upgrades.autofit();
     Ok(())
 }
+
+fn add_obstacles_sheet(
+    workbook: &mut Workbook,
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name("Obstacles")?;
+
+    let mut row = 1;
+    let singles_col = 3;
+    for item in inventory.keys() {
+        if item.r#type == ItemType::Obstacle {
+            let record = match ObstacleRecord::build(&item.xws, 1, data, catalog) {
+                Ok(r) => r,
+                Err(_) => {
+                    eprintln!("xslx: missing obstacle {}", item.xws);
+                    continue;
+                }
+            };
+
+            sheet.write(row, 0, &record.name)?;
+            sheet.write(row, 1, &record.r#type)?;
+            sheet.write_dynamic_formula(
+                row,
+                2,
+                total_func(item, row_col_to_cell(row, singles_col), catalog).as_str(),
+            )?;
+            sheet.write(
+                row,
+                singles_col,
+                *collection.singles.get(item).unwrap_or(&0) as i32,
+            )?;
+            sheet.write(row, 4, &record.xws)?;
+            sheet.write(row, 5, record.sources.unwrap_or_default())?;
+
+            row += 1;
+        }
+    }
+    let columns = vec![
+        TableColumn::new()
+            .set_header("Name")
+            .set_total_label("Totals"),
+        TableColumn::new().set_header("Type"),
+        TableColumn::new()
+            .set_header("Total")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new()
+            .set_header("Singles")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new()
+            .set_header("XWS")
+            .set_total_function(TableFunction::Count),
+        TableColumn::new().set_header("Sources"),
+    ];
+    let mut table = Table::new();
+    let table = table
+        .set_name("obstacleTable")
+        .set_style(TableStyle::Medium8)
+        .set_columns(&columns)
+        .set_total_row(true);
+    sheet.add_table(0, 0, row, columns.len() as u16 - 1, table)?;
+    sheet.autofit();
+    Ok(())
+}
+
+fn add_damage_decks_sheet(
+    workbook: &mut Workbook,
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name("Damage Decks")?;
+
+    let mut row = 1;
+    let singles_col = 5;
+    for item in inventory.keys() {
+        if item.r#type == ItemType::Damage {
+            let record = match DamageDeckRecord::build(&item.xws, 1, data, catalog) {
+                Ok(r) => r,
+                Err(_) => {
+                    eprintln!("xslx: missing damage card {}", item.xws);
+                    continue;
+                }
+            };
+
+            sheet.write(row, 0, &record.name)?;
+            sheet.write(row, 1, &record.deck)?;
+            sheet.write(row, 2, &record.r#type)?;
+            sheet.write(row, 3, record.amount)?;
+            sheet.write_dynamic_formula(
+                row,
+                4,
+                total_func(item, row_col_to_cell(row, singles_col), catalog).as_str(),
+            )?;
+            sheet.write(
+                row,
+                singles_col,
+                *collection.singles.get(item).unwrap_or(&0) as i32,
+            )?;
+            sheet.write(row, 6, &record.xws)?;
+            sheet.write(row, 7, record.sources.unwrap_or_default())?;
+
+            row += 1;
+        }
+    }
+    let columns = vec![
+        TableColumn::new()
+            .set_header("Name")
+            .set_total_label("Totals"),
+        TableColumn::new().set_header("Deck"),
+        TableColumn::new().set_header("Type"),
+        TableColumn::new()
+            .set_header("Amount")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new()
+            .set_header("Total")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new()
+            .set_header("Singles")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new()
+            .set_header("XWS")
+            .set_total_function(TableFunction::Count),
+        TableColumn::new().set_header("Sources"),
+    ];
+    let mut table = Table::new();
+    let table = table
+        .set_name("damageDeckTable")
+        .set_style(TableStyle::Medium9)
+        .set_columns(&columns)
+        .set_total_row(true);
+    sheet.add_table(0, 0, row, columns.len() as u16 - 1, table)?;
+    sheet.autofit();
+    Ok(())
+}
+
+/// The components, with the `Ships` that use each one to compare against, e.g.
+/// the number of ships a dial is for.
+fn add_components_sheet(
+    workbook: &mut Workbook,
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name("Components")?;
+
+    let mut row = 1;
+    let singles_col = 3;
+    for item in components::items(inventory) {
+        let record = match ComponentRecord::build(
+            &item,
+            *inventory.get(&item).unwrap_or(&0),
+            inventory,
+            data,
+            catalog,
+        ) {
+            Ok(r) => r,
+            Err(_) => {
+                eprintln!("xslx: missing {} {}", item.r#type.as_ref(), item.xws);
+                continue;
+            }
+        };
+
+        sheet.write(row, 0, &record.name)?;
+        sheet.write(row, 1, item.r#type.as_ref())?;
+        sheet.write_dynamic_formula(
+            row,
+            2,
+            total_func(&item, row_col_to_cell(row, singles_col), catalog).as_str(),
+        )?;
+        sheet.write(
+            row,
+            singles_col,
+            *collection.singles.get(&item).unwrap_or(&0) as i32,
+        )?;
+        sheet.write(row, 4, record.ships)?;
+        sheet.write(row, 5, &record.xws)?;
+        sheet.write(row, 6, record.sources.unwrap_or_default())?;
+
+        row += 1;
+    }
+    let columns = vec![
+        TableColumn::new()
+            .set_header("Name")
+            .set_total_label("Totals"),
+        TableColumn::new().set_header("Type"),
+        TableColumn::new()
+            .set_header("Total")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new()
+            .set_header("Singles")
+            .set_total_function(TableFunction::Sum),
+        TableColumn::new().set_header("Ships"),
+        TableColumn::new()
+            .set_header("XWS")
+            .set_total_function(TableFunction::Count),
+        TableColumn::new().set_header("Sources"),
+    ];
+    let mut table = Table::new();
+    let table = table
+        .set_name("componentTable")
+        .set_style(TableStyle::Medium10)
+        .set_columns(&columns)
+        .set_total_row(true);
+    sheet.add_table(0, 0, row, columns.len() as u16 - 1, table)?;
+    sheet.autofit();
+    Ok(())
+}
+
+/// The models and pilot cards of each ship at the time the sheet was
+/// generated, since it doesn't update with the `Singles` columns.
+fn add_gaps_sheet(
+    workbook: &mut Workbook,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name("Gaps")?;
+
+    let mut row = 1;
+    for g in gaps::build(inventory, data) {
+        sheet.write(row, 0, &g.name)?;
+        sheet.write(row, 1, g.models)?;
+        sheet.write(row, 2, value::format_factions(&g.cards))?;
+        sheet.write(row, 3, g.factions_without_cards.join(","))?;
+        sheet.write(row, 4, g.unusable_pilots.join(","))?;
+        sheet.write(row, 5, &g.xws)?;
+        row += 1;
+    }
+    let columns = vec![
+        TableColumn::new().set_header("Name"),
+        TableColumn::new().set_header("Models"),
+        TableColumn::new().set_header("Pilot Cards"),
+        TableColumn::new().set_header("Factions Without Cards"),
+        TableColumn::new().set_header("Unusable Pilots"),
+        TableColumn::new().set_header("XWS"),
+    ];
+    let mut table = Table::new();
+    let table = table
+        .set_name("gapTable")
+        .set_style(TableStyle::Medium11)
+        .set_columns(&columns);
+    sheet.add_table(0, 0, row.max(2) - 1, columns.len() as u16 - 1, table)?;
+    sheet.autofit();
+    Ok(())
+}
+
+/// The value of each unowned expansion at the time the sheet was generated,
+/// since it doesn't update with the `Owned` column.
+fn add_value_sheet(
+    workbook: &mut Workbook,
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name("Value")?;
+
+    let mut row = 1;
+    for v in value::build(catalog, data, collection, inventory) {
+        sheet.write(row, 0, &v.name)?;
+        sheet.write(row, 1, &v.sku)?;
+        sheet.write(row, 2, v.wave)?;
+        sheet.write(row, 3, v.kind.as_ref())?;
+        sheet.write(row, 4, v.new_unique())?;
+        sheet.write(row, 5, v.new_ships)?;
+        sheet.write(row, 6, v.new_pilots)?;
+        sheet.write(row, 7, v.new_upgrades)?;
+        sheet.write(row, 8, v.new_components)?;
+        sheet.write(row, 9, v.duplicates.len() as u32)?;
+        sheet.write(row, 10, value::format_factions(&v.factions))?;
+        sheet.write(
+            row,
+            11,
+            v.new
+                .iter()
+                .map(|(i, _)| i.xws.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )?;
+        row += 1;
+    }
+    let columns = vec![
+        TableColumn::new().set_header("Name"),
+        TableColumn::new().set_header("SKU"),
+        TableColumn::new().set_header("Wave"),
+        TableColumn::new().set_header("Kind"),
+        TableColumn::new().set_header("New Unique"),
+        TableColumn::new().set_header("New Ships"),
+        TableColumn::new().set_header("New Pilots"),
+        TableColumn::new().set_header("New Upgrades"),
+        TableColumn::new().set_header("New Components"),
+        TableColumn::new().set_header("Duplicates"),
+        TableColumn::new().set_header("New Pilot Factions"),
+        TableColumn::new().set_header("New Items"),
+    ];
+    let mut table = Table::new();
+    let table = table
+        .set_name("valueTable")
+        .set_style(TableStyle::Medium6)
+        .set_columns(&columns);
+    sheet.add_table(0, 0, row.max(2) - 1, columns.len() as u16 - 1, table)?;
+    sheet.autofit();
+    Ok(())
+}
+
+/// The owned upgrades that fit each slot type of the owned pilots at the time
+/// the sheet was generated.
+fn add_availability_sheet(
+    workbook: &mut Workbook,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name("Availability")?;
+
+    let mut row = 1;
+    for a in usable::availability(data, inventory) {
+        sheet.write(row, 0, &a.name)?;
+        sheet.write(row, 1, &a.ship)?;
+        sheet.write(row, 2, &a.faction)?;
+        sheet.write(row, 3, format!("{:?}", a.slot))?;
+        sheet.write(row, 4, a.slots as u32)?;
+        sheet.write(row, 5, a.upgrades as u32)?;
+        sheet.write(row, 6, a.cards)?;
+        sheet.write(row, 7, &a.pilot)?;
+        row += 1;
+    }
+    let columns = vec![
+        TableColumn::new().set_header("Pilot"),
+        TableColumn::new().set_header("Ship"),
+        TableColumn::new().set_header("Faction"),
+        TableColumn::new().set_header("Slot"),
+        TableColumn::new().set_header("Slots"),
+        TableColumn::new().set_header("Unique Upgrades"),
+        TableColumn::new().set_header("Upgrade Cards"),
+        TableColumn::new().set_header("XWS"),
+    ];
+    let mut table = Table::new();
+    let table = table
+        .set_name("availabilityTable")
+        .set_style(TableStyle::Medium12)
+        .set_columns(&columns);
+    sheet.add_table(0, 0, row.max(2) - 1, columns.len() as u16 - 1, table)?;
+    sheet.autofit();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn collection() -> Collection {
+        let mut c = Collection::default();
+        c.add_expansion("swz25", 2);
+        c.add_single(
+            Item {
+                r#type: ItemType::Pilot,
+                xws: "poedameron".to_owned(),
+            },
+            1,
+        );
+        c
+    }
+
+    #[test]
+    fn test_collection_json() {
+        let c = collection();
+        let json = serde_json::to_string(&c).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"skus":{"swz25":2},"singles":[{"type":"pilot","xws":"poedameron","count":1}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Collection>(&json).unwrap(), c);
+    }
+
+    #[test]
+    fn test_collection_toml() {
+        let c = collection();
+        let buffer = toml::to_string(&c).unwrap();
+        assert_eq!(toml::from_str::<Collection>(&buffer).unwrap(), c);
+    }
+
+    #[test]
+    fn test_collection_version() {
+        let json = r#"{"version": 2, "skus": {}}"#;
+        assert!(serde_json::from_str::<Collection>(json).is_err());
+    }
+
+    #[test]
+    fn test_merge_subtract() {
+        let mut c = collection();
+        c.merge(&collection());
+        assert_eq!(c.skus["swz25"], 4);
+        assert_eq!(c.singles.values().sum::<u32>(), 2);
+
+        c.subtract(&collection());
+        assert_eq!(c, collection());
+        c.subtract(&collection());
+        c.subtract(&collection());
+        assert_eq!(c, Collection::default());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut c = collection();
+        assert_eq!(c.remove_expansion("swz25", 1), 1);
+        assert_eq!(c.skus.get("swz25"), Some(&1));
+        assert_eq!(c.remove_expansion("swz25", 5), 1);
+        assert!(c.skus.is_empty());
+        assert_eq!(c.remove_expansion("swz25", 1), 0);
+    }
+
+    #[test]
+    fn test_pilot_record() {
+        let data = Data {
+            ships: vec![xwingdata2::fixtures::ship(
+                "T-65 X-wing",
+                "t65xwing",
+                "rebelalliance",
+                vec![serde_json::from_str(
+                    r#"{"name": "Luke Skywalker", "xws": "lukeskywalker", "initiative": 5,
+                        "limited": 1, "cost": 62, "slots": ["Talent", "Astromech"],
+                        "force": {"value": 2, "recovers": 1},
+                        "shipAbility": {"name": "Full Throttle", "text": "Boost."}}"#,
+                )
+                .unwrap()],
+            )],
+            ..Default::default()
+        };
+
+        let r = PilotRecord::build("lukeskywalker", 1, &data, &Catalog::default()).unwrap();
+        assert_eq!(r.limited, 1);
+        assert_eq!(r.cost, Some(62));
+        assert_eq!(r.slots, "Talent,Astromech");
+        assert_eq!(r.force.unwrap().value, 2);
+        assert_eq!(r.ship_ability.unwrap(), "Full Throttle: Boost.");
+    }
+
+    #[test]
+    fn test_upgrade_sides() {
+        let data = Data {
+            upgrades: vec![serde_json::from_str(
+                r#"{"name": "Pivot Wing", "xws": "pivotwing", "sides": [
+                    {"title": "Pivot Wing (Closed)", "type": "Configuration",
+                     "slots": ["Configuration"]},
+                    {"title": "Pivot Wing (Open)", "type": "Configuration",
+                     "slots": ["Configuration"], "charges": {"value": 1}}
+                ]}"#,
+            )
+            .unwrap()],
+            ..Default::default()
+        };
+        let catalog = Catalog::default();
+
+        let card = UpgradeRecord::build("pivotwing", 2, &data, &catalog).unwrap();
+        assert_eq!(card.name, "Pivot Wing");
+        assert_eq!(card.side, None);
+
+        let sides = UpgradeRecord::build_sides("pivotwing", 2, &data, &catalog).unwrap();
+        assert_eq!(
+            sides.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["Pivot Wing (Closed)", "Pivot Wing (Open)"]
+        );
+        assert_eq!(sides[1].side, Some(1));
+        // the card is only counted once
+        assert_eq!(sides[0].count, 2);
+        assert_eq!(sides[1].count, 0);
+        assert_eq!(sides[1].charges.as_ref().unwrap().value, 1);
+        assert_eq!(sides[1].all_slot_types, "Configuration");
+    }
+}
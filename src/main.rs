@@ -1,36 +1,177 @@
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use std::{fs::File, path::PathBuf};
 
 use strum::EnumString;
-use xwingtmg2_inventory_rs::Records;
-use xwingtmg2_inventory_rs::{expansions::Catalog, xwingdata2::Data, yasb2, Collection};
+use xwingtmg2_inventory_rs::{
+    compatibility::Compatibility,
+    components, csv_export, diff,
+    expansions::{Catalog, Item, ItemType},
+    gaps, plan,
+    squad::{self, Mode, Report, Squad},
+    trade::{self, Keep, KeepRules, TradeLists},
+    usable, value, xlsx,
+    xwingdata2::Data,
+    yasb2, Collection, Error,
+};
+use xwingtmg2_inventory_rs::{Inventory, Records, SheetOptions};
 
 const HELP: &str = "\
 xwingtmg2-inventory
 
 USAGE:
   xwingtmg2-inventory [options]
+  xwingtmg2-inventory add-expansion -c <collection> <sku> [count]
+  xwingtmg2-inventory add-single -c <collection> <type> <xws> [count]
+  xwingtmg2-inventory remove-expansion -c <collection> <sku> [count]
+  xwingtmg2-inventory remove-single -c <collection> <type> <xws> [count]
+  xwingtmg2-inventory check -c <collection> [--mode <mode>] <squad.json>...
+  xwingtmg2-inventory plan -c <collection> [plan options] [squad.json]...
+  xwingtmg2-inventory value -c <collection> [--limit <n>]
+  xwingtmg2-inventory gaps -c <collection> [-f json]
+  xwingtmg2-inventory usable -c <collection> --pilot <xws> [-f json]
+  xwingtmg2-inventory diff [-f json] <collection> <collection>
+  xwingtmg2-inventory trade -c <collection> [trade options]
+  xwingtmg2-inventory yasb unmapped -c <yasb collection> [-f json]
+
+SUBCOMMANDS:
+  add-*, remove-*       Edit a collection saved in this tool's own .json or
+                        .toml format, creating it if it doesn't exist. The
+                        type is one of ship, pilot, upgrade, obstacle,
+                        damage, dial, base, token or template and the count
+                        defaults to 1.
+  check                 Lists the ships, dials, pilots and upgrades missing
+                        from the collection to build one or more XWS squads.
+                        With --mode simultaneous (default) the squads are all
+                        fielded at once, with --mode sequential one at a time.
+  plan                  Lists the expansions to buy to get everything in the
+                        squads and the following options that isn't already
+                        in the collection:
+    --faction <xws>       every pilot, ship and faction restricted upgrade
+    --item <type:xws[:n]> specific items, can be repeated
+    --prices <file>       a .json or .toml map of sku to price to minimize
+                          the cost instead of the number of expansions,
+                          unpriced expansions are skipped
+    --exclude <sku,...>   expansions that can't be bought
+  value                 Ranks the expansions that aren't in the collection by
+                        how many new unique items they would add.
+  gaps                  Lists the ship models without pilot cards in one of
+                        their factions and the pilot cards without a model.
+                        With -f json every ship is printed as json.
+  usable                Lists the upgrades the --pilot can equip by slot,
+                        with the number owned. With -f json they are
+                        printed as json.
+  diff                  Lists the expansion and item count changes from the
+                        first collection to the second, in any format -c
+                        accepts. With -f json the changes are printed as json.
+  trade                 Writes the ships, pilots and upgrades beyond the
+                        number to keep, and with --with or --wants the ones
+                        that could be traded, as trades.json, trade_*.csv or
+                        XWingTMG2_Trades.xlsx:
+    --keep <n|list>       how many of everything to keep (default: 1), list
+                          keeps the most a single list can use
+    --keep-ships <n|list>, --keep-pilots <n|list>, --keep-upgrades <n|list>
+                          overrides --keep for one type
+    --with <collection>   another collection to match surplus both ways
+    --wants <collection>  another collector's want list, in any format -c
+                          accepts
+  yasb unmapped         Lists the singles in a YASB collection whose names
+                        don't match a ship, pilot or upgrade, to add to a
+                        --yasb-names file. With -f json they are printed as
+                        json.
 
 FLAGS:
   -h, --help            Prints help information
-  -f, --format          json or xlsx (default: xlsx)
-  -c, --collection      A YASB collection in YASB's json format
+  -f, --format          json, csv, sqlite or xlsx (default: xlsx)
+  --long                With csv, write a single inventory.csv in the long
+                        format, one row per item and column, instead of
+                        a file per type, e.g. ships.csv
+  -O, --output          Where to write the output instead of the default file
+                        name, or - for stdout, except for sqlite. For csv
+                        without --long and csv trade lists, a directory to
+                        write the files to
+  -c, --collection      A collection in this tool's .json or .toml format,
+                        a YASB collection in YASB's json format, or a
+                        previously generated and filled in .xlsx
   -o, --only-owned      Don't include unowned expansions and contents
+  --dials               Add a Dial column with each ship's maneuvers to the
+                        Ships sheet, or a dial to the json ships, to match
+                        loose dials to their ships
+  --upgrade-sides       A row for each side of double-sided upgrades, like
+                        configurations, named after the side, instead of
+                        one per card
+  --availability        Add an Availability sheet with the number of owned
+                        upgrades that fit each slot of the owned pilots
+  --data-dir            Path to an xwing-data2 checkout to use instead of the
+                        embedded copy
+  --catalog             Path to an expansions.json to use instead of the
+                        embedded copy
+  --compatibility       Path to a compatibility.json of legacy ship models
+                        and the ships they can be used as, to use instead of
+                        the embedded copy
+  --yasb-names          Path to a .json or .toml map of YASB names to xws
+                        ids, with ship, pilot and upgrade sections, that
+                        adds to and overrides the embedded names.json
 ";
 
 #[derive(PartialEq, EnumString)]
 enum Format {
     #[strum(serialize = "json", serialize = "JSON")]
     Json,
+    #[strum(serialize = "csv", serialize = "CSV")]
+    Csv,
+    #[cfg(feature = "sqlite")]
+    #[strum(serialize = "sqlite", serialize = "SQLITE")]
+    Sqlite,
     #[strum(serialize = "xlsx", serialize = "XLSX")]
     Xlsx,
 }
 
+enum Command {
+    Generate,
+    Check(Vec<PathBuf>, Mode),
+    Plan(PlanArgs),
+    Value(Option<usize>),
+    Gaps,
+    Usable(String),
+    Diff(PathBuf, PathBuf),
+    Trade(TradeArgs),
+    YasbUnmapped,
+    AddExpansion(String, u32),
+    AddSingle(Item, u32),
+    RemoveExpansion(String, u32),
+    RemoveSingle(Item, u32),
+}
+
+struct PlanArgs {
+    squads: Vec<PathBuf>,
+    faction: Option<String>,
+    items: Vec<(Item, u32)>,
+    prices: Option<PathBuf>,
+    exclude: Vec<String>,
+}
+
+struct TradeArgs {
+    keep: KeepRules,
+    with: Option<PathBuf>,
+    wants: Option<PathBuf>,
+}
+
 struct Args {
+    command: Command,
     only_owned: bool,
-    collection_json: Option<PathBuf>,
+    dials: bool,
+    upgrade_sides: bool,
+    availability: bool,
+    collection: Option<PathBuf>,
     format: Format,
+    long: bool,
+    output: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    catalog_json: Option<PathBuf>,
+    compatibility_json: Option<PathBuf>,
+    yasb_names: Option<PathBuf>,
 }
 
 fn parse_args() -> Result<Args, pico_args::Error> {
@@ -42,12 +183,110 @@ fn parse_args() -> Result<Args, pico_args::Error> {
         std::process::exit(0);
     }
 
-    let args = Args {
+    let subcommand = pargs.subcommand()?;
+
+    let mut args = Args {
+        command: Command::Generate,
         only_owned: pargs.contains(["-l", "--only-owned"]),
-        collection_json: pargs.opt_value_from_os_str(["-c", "--collection"], parse_path)?,
+        dials: pargs.contains("--dials"),
+        upgrade_sides: pargs.contains("--upgrade-sides"),
+        availability: pargs.contains("--availability"),
+        collection: pargs.opt_value_from_os_str(["-c", "--collection"], parse_path)?,
         format: pargs
             .opt_value_from_str::<_, Format>(["-f", "--format"])?
             .unwrap_or(Format::Xlsx),
+        long: pargs.contains("--long"),
+        output: pargs.opt_value_from_os_str(["-O", "--output"], parse_path)?,
+        data_dir: pargs.opt_value_from_os_str("--data-dir", parse_path)?,
+        catalog_json: pargs.opt_value_from_os_str("--catalog", parse_path)?,
+        compatibility_json: pargs.opt_value_from_os_str("--compatibility", parse_path)?,
+        yasb_names: pargs.opt_value_from_os_str("--yasb-names", parse_path)?,
+    };
+
+    args.command = match subcommand.as_deref() {
+        None => Command::Generate,
+        Some("check") => {
+            let mode = pargs
+                .opt_value_from_str("--mode")?
+                .unwrap_or(Mode::Simultaneous);
+            let mut squads = vec![pargs.free_from_os_str(parse_path)?];
+            while let Some(p) = pargs.opt_free_from_os_str(parse_path)? {
+                squads.push(p);
+            }
+            Command::Check(squads, mode)
+        }
+        Some("diff") => Command::Diff(
+            pargs.free_from_os_str(parse_path)?,
+            pargs.free_from_os_str(parse_path)?,
+        ),
+        Some("trade") => {
+            let keep: Keep = pargs
+                .opt_value_from_str("--keep")?
+                .unwrap_or(Keep::Count(1));
+            Command::Trade(TradeArgs {
+                keep: KeepRules {
+                    ships: pargs.opt_value_from_str("--keep-ships")?.unwrap_or(keep),
+                    pilots: pargs.opt_value_from_str("--keep-pilots")?.unwrap_or(keep),
+                    upgrades: pargs.opt_value_from_str("--keep-upgrades")?.unwrap_or(keep),
+                },
+                with: pargs.opt_value_from_os_str("--with", parse_path)?,
+                wants: pargs.opt_value_from_os_str("--wants", parse_path)?,
+            })
+        }
+        Some("value") => Command::Value(pargs.opt_value_from_str("--limit")?),
+        Some("gaps") => Command::Gaps,
+        Some("usable") => Command::Usable(pargs.value_from_str("--pilot")?),
+        Some("yasb") => match pargs.free_from_str::<String>()?.as_str() {
+            "unmapped" => Command::YasbUnmapped,
+            s => {
+                eprintln!("Error: unknown yasb subcommand: {}.", s);
+                std::process::exit(1);
+            }
+        },
+        Some("plan") => {
+            let mut plan = PlanArgs {
+                faction: pargs.opt_value_from_str("--faction")?,
+                items: pargs.values_from_fn("--item", parse_item_count)?,
+                prices: pargs.opt_value_from_os_str("--prices", parse_path)?,
+                exclude: pargs
+                    .values_from_str::<_, String>("--exclude")?
+                    .iter()
+                    .flat_map(|e| e.split(','))
+                    .map(|e| e.trim().to_owned())
+                    .collect(),
+                squads: vec![],
+            };
+            while let Some(p) = pargs.opt_free_from_os_str(parse_path)? {
+                plan.squads.push(p);
+            }
+            Command::Plan(plan)
+        }
+        Some("add-expansion") => Command::AddExpansion(
+            pargs.free_from_str()?,
+            pargs.opt_free_from_str()?.unwrap_or(1),
+        ),
+        Some("add-single") => Command::AddSingle(
+            Item {
+                r#type: pargs.free_from_str()?,
+                xws: pargs.free_from_str()?,
+            },
+            pargs.opt_free_from_str()?.unwrap_or(1),
+        ),
+        Some("remove-expansion") => Command::RemoveExpansion(
+            pargs.free_from_str()?,
+            pargs.opt_free_from_str()?.unwrap_or(1),
+        ),
+        Some("remove-single") => Command::RemoveSingle(
+            Item {
+                r#type: pargs.free_from_str()?,
+                xws: pargs.free_from_str()?,
+            },
+            pargs.opt_free_from_str()?.unwrap_or(1),
+        ),
+        Some(s) => {
+            eprintln!("Error: unknown subcommand: {}.", s);
+            std::process::exit(1);
+        }
     };
 
     // It's up to the caller what to do with the remaining arguments.
@@ -59,10 +298,618 @@ fn parse_args() -> Result<Args, pico_args::Error> {
     Ok(args)
 }
 
+/// Parses `type:xws[:count]`, e.g. `pilot:poedameron:2`.
+fn parse_item_count(s: &str) -> Result<(Item, u32), String> {
+    let mut parts = s.split(':');
+    let (r#type, xws) = match (parts.next(), parts.next()) {
+        (Some(t), Some(x)) => (t.parse().map_err(|_| format!("unknown type: {}", t))?, x),
+        _ => return Err(format!("expected type:xws[:count], got {}", s)),
+    };
+    let count = match parts.next() {
+        None => 1,
+        Some(c) => c.parse().map_err(|_| format!("invalid count: {}", c))?,
+    };
+    Ok((
+        Item {
+            r#type,
+            xws: xws.to_owned(),
+        },
+        count,
+    ))
+}
+
 fn parse_path(s: &std::ffi::OsStr) -> Result<std::path::PathBuf, &'static str> {
     Ok(s.into())
 }
 
+#[cfg(feature = "embedded")]
+fn load_data(data_dir: Option<PathBuf>) -> Result<Data, Error> {
+    match data_dir {
+        Some(p) => Data::load_from_manifest(&p),
+        None => Data::load_embedded(),
+    }
+}
+
+#[cfg(not(feature = "embedded"))]
+fn load_data(data_dir: Option<PathBuf>) -> Result<Data, Error> {
+    Data::load_from_manifest(&data_dir.unwrap_or(PathBuf::from("xwing-data2")))
+}
+
+#[cfg(feature = "embedded")]
+fn load_catalog(catalog_json: Option<PathBuf>) -> Result<Catalog, Error> {
+    match catalog_json {
+        Some(p) => Catalog::load_from_path(&p),
+        None => Catalog::load_embedded(),
+    }
+}
+
+#[cfg(not(feature = "embedded"))]
+fn load_catalog(catalog_json: Option<PathBuf>) -> Result<Catalog, Error> {
+    Catalog::load_from_path(
+        &catalog_json.unwrap_or(PathBuf::from("./src/expansions/expansions.json")),
+    )
+}
+
+#[cfg(feature = "embedded")]
+fn load_compatibility(compatibility_json: Option<PathBuf>) -> Result<Compatibility, Error> {
+    match compatibility_json {
+        Some(p) => Compatibility::load_from_path(&p),
+        None => Compatibility::load_embedded(),
+    }
+}
+
+#[cfg(not(feature = "embedded"))]
+fn load_compatibility(compatibility_json: Option<PathBuf>) -> Result<Compatibility, Error> {
+    Compatibility::load_from_path(
+        &compatibility_json.unwrap_or(PathBuf::from("./src/compatibility/compatibility.json")),
+    )
+}
+
+#[cfg(feature = "embedded")]
+fn load_yasb_names(yasb_names: Option<PathBuf>) -> Result<yasb2::NameMap, Error> {
+    let mut names = yasb2::NameMap::load_embedded()?;
+    if let Some(p) = yasb_names {
+        names.extend(yasb2::NameMap::load_from_path(&p)?);
+    }
+    Ok(names)
+}
+
+#[cfg(not(feature = "embedded"))]
+fn load_yasb_names(yasb_names: Option<PathBuf>) -> Result<yasb2::NameMap, Error> {
+    let mut names = yasb2::NameMap::load_from_path(Path::new("./src/yasb2/names.json"))?;
+    if let Some(p) = yasb_names {
+        names.extend(yasb2::NameMap::load_from_path(&p)?);
+    }
+    Ok(names)
+}
+
+/// What a YASB collection's names are checked and turned into xws ids with.
+struct YasbImport<'a> {
+    names: &'a yasb2::NameMap,
+    data: &'a Data,
+    compatibility: &'a Compatibility,
+}
+
+/// Loads either a spreadsheet previously generated by this tool or a YASB
+/// collection, based on the extension.
+fn load_collection(path: &Path, catalog: &Catalog, yasb: &YasbImport) -> Result<Collection, Error> {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("xlsx"))
+    {
+        return xlsx::load(path);
+    }
+
+    // YASB's json is wrapped in a `collection` object, this tool's isn't.
+    let is_yasb = fs::read_to_string(path)
+        .ok()
+        .and_then(|b| serde_json::from_str::<serde_json::Value>(&b).ok())
+        .is_some_and(|v| v.get("collection").is_some());
+    if !is_yasb {
+        return Collection::load(path);
+    }
+
+    let yasb_coll = yasb2::Collection::load(path)?;
+    let (skus, missing) = yasb_coll.expansion_skus(catalog)?;
+
+    eprintln!("Not found expansions (probably 1.0, but for debugging):");
+    for n in missing {
+        eprintln!("- {}", n);
+    }
+
+    let (singles, unmapped) =
+        yasb_coll.singles_as_xws(yasb.names, yasb.data, yasb.compatibility)?;
+    if !unmapped.is_empty() {
+        eprintln!("Not found singles, kept as is (see yasb unmapped):");
+        for u in &unmapped {
+            eprintln!("- {}", format_unmapped(u));
+        }
+    }
+
+    Ok(Collection { skus, singles })
+}
+
+/// Applies an add/remove subcommand to a native collection file.
+fn edit_collection(
+    command: Command,
+    path: &Path,
+    catalog: &Catalog,
+    data: &Data,
+) -> Result<(), Error> {
+    let mut collection = if path.exists() {
+        Collection::load(path)?
+    } else {
+        Collection::default()
+    };
+
+    match command {
+        Command::Generate
+        | Command::Check(..)
+        | Command::Plan(_)
+        | Command::Value(_)
+        | Command::Gaps
+        | Command::Usable(_)
+        | Command::Diff(..)
+        | Command::Trade(_)
+        | Command::YasbUnmapped => (),
+        Command::AddExpansion(sku, count) => {
+            if !catalog.expansions.contains_key(&sku) {
+                return Err(Error::UnknownSku(sku));
+            }
+            collection.add_expansion(&sku, count);
+            println!("added {} {}", count, sku);
+        }
+        Command::AddSingle(item, count) => {
+            let known = match item.r#type {
+                ItemType::Ship => data.get_ship_model(&item.xws).is_some(),
+                ItemType::Pilot => data.get_pilot(&item.xws).is_some(),
+                ItemType::Upgrade => data.get_upgrade(&item.xws).is_some(),
+                t if t.is_component() => components::name(&item, data).is_some(),
+                _ => catalog.has_item(&item),
+            };
+            if !known {
+                return Err(Error::UnknownXws(item));
+            }
+            println!("added {} {}", count, item.xws);
+            collection.add_single(item, count);
+        }
+        Command::RemoveExpansion(sku, count) => {
+            let removed = collection.remove_expansion(&sku, count);
+            println!("removed {} {}", removed, sku);
+        }
+        Command::RemoveSingle(item, count) => {
+            let removed = collection.remove_single(&item, count);
+            println!("removed {} {}", removed, item.xws);
+        }
+    }
+
+    collection.save(path)
+}
+
+/// Prints every item the collection is short of for the squads, returning
+/// whether they can be built.
+fn check_squads(
+    paths: &[PathBuf],
+    mode: Mode,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<bool, Error> {
+    let mut demands = vec![];
+    for p in paths {
+        demands.push(Squad::load(p)?.demand(data, squad::tracks_dials(inventory))?);
+    }
+
+    let report = Report::build(&demands, mode, inventory);
+    for d in report.shortfalls() {
+        let per_squad = d
+            .per_squad
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        println!(
+            "{:?} {}: need {} ({}), own {}",
+            d.item.r#type,
+            d.item.xws,
+            d.needed,
+            per_squad.join(match mode {
+                Mode::Simultaneous => "+",
+                Mode::Sequential => "|",
+            }),
+            d.owned
+        );
+    }
+    Ok(report.is_buildable())
+}
+
+/// Prints the ships with gaps, or every ship as json.
+fn print_gaps(inventory: &Inventory, data: &Data, json: bool) -> Result<(), Error> {
+    let gaps = gaps::build(inventory, data);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&gaps).map_err(Error::Json)?
+        );
+        return Ok(());
+    }
+
+    for g in gaps.iter().filter(|g| g.is_gap()) {
+        if g.models == 0 {
+            println!(
+                "{} ({}): no models for {}",
+                g.name,
+                g.xws,
+                g.unusable_pilots.join(", ")
+            );
+        } else {
+            println!(
+                "{} ({}): {} models, no pilot cards for {}",
+                g.name,
+                g.xws,
+                g.models,
+                g.factions_without_cards.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints the upgrades the pilot can equip, by slot.
+fn print_usable(pilot: &str, inventory: &Inventory, data: &Data, json: bool) -> Result<(), Error> {
+    let groups = usable::for_pilot(pilot, data, inventory)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&groups).map_err(Error::Json)?
+        );
+        return Ok(());
+    }
+
+    for g in &groups {
+        println!(
+            "{:?} ({} slots): {}/{} owned",
+            g.slot,
+            g.slots,
+            g.owned_unique(),
+            g.upgrades.len()
+        );
+        for u in &g.upgrades {
+            println!("- {} ({}): {}", u.name, u.xws, u.owned);
+        }
+    }
+    Ok(())
+}
+
+/// A not found YASB single and the suggested xws ids, if any.
+fn format_unmapped(u: &yasb2::Unmapped) -> String {
+    let mut s = format!("{:?} \"{}\": {}", u.r#type, u.name, u.xws);
+    if !u.suggestions.is_empty() {
+        s.push_str(&format!(", did you mean {}?", u.suggestions.join(", ")));
+    }
+    s
+}
+
+/// Prints the YASB singles that don't map to anything in xwing-data2.
+fn print_unmapped(path: &Path, yasb: &YasbImport, json: bool) -> Result<(), Error> {
+    let (_, unmapped) =
+        yasb2::Collection::load(path)?.singles_as_xws(yasb.names, yasb.data, yasb.compatibility)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&unmapped).map_err(Error::Json)?
+        );
+        return Ok(());
+    }
+
+    for u in &unmapped {
+        println!("{}", format_unmapped(u));
+    }
+    if unmapped.is_empty() {
+        println!("every name is mapped");
+    }
+    Ok(())
+}
+
+/// Prints the changes from collection `a` to `b`, either as a list or json.
+fn diff_collections(
+    a: &Path,
+    b: &Path,
+    catalog: &Catalog,
+    yasb: &YasbImport,
+    json: bool,
+) -> Result<(), Error> {
+    let d = diff::diff(
+        &load_collection(a, catalog, yasb)?,
+        &load_collection(b, catalog, yasb)?,
+        catalog,
+        yasb.compatibility,
+    );
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&d).map_err(Error::Json)?);
+        return Ok(());
+    }
+
+    for s in &d.skus {
+        let name = catalog
+            .expansions
+            .get(&s.sku)
+            .map_or("unknown", |e| e.name.as_str());
+        println!(
+            "{:+} {} ({}): {} -> {}",
+            s.after as i64 - s.before as i64,
+            name,
+            s.sku,
+            s.before,
+            s.after
+        );
+    }
+    for i in &d.items {
+        println!(
+            "{:+} {:?} {}: {} -> {}",
+            i.after as i64 - i.before as i64,
+            i.r#type,
+            i.xws,
+            i.before,
+            i.after
+        );
+    }
+    if d.is_empty() {
+        println!("no differences");
+    }
+    Ok(())
+}
+
+/// Writes the trade lists in the requested format.
+fn write_trades(
+    args: &TradeArgs,
+    format: &Format,
+    output: Option<&Path>,
+    catalog: &Catalog,
+    yasb: &YasbImport,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<(), Error> {
+    let other = match (&args.with, &args.wants) {
+        (Some(p), _) => Some(trade::Other::Collection(
+            load_collection(p, catalog, yasb)?
+                .inventory(catalog, yasb.compatibility)
+                .0,
+        )),
+        (None, Some(p)) => Some(trade::Other::Wants(
+            load_collection(p, catalog, yasb)?
+                .inventory(catalog, yasb.compatibility)
+                .0,
+        )),
+        (None, None) => None,
+    };
+
+    let (lists, errors) = TradeLists::build(inventory, other.as_ref(), data, catalog, &args.keep);
+    for e in errors {
+        eprintln!("{}", e);
+    }
+    for (name, records) in [
+        ("surplus", Some(&lists.surplus)),
+        ("give", lists.give.as_ref()),
+        ("receive", lists.receive.as_ref()),
+    ] {
+        if let Some(r) = records {
+            eprintln!(
+                "{}: {} ships, {} pilots, {} upgrades",
+                name,
+                r.ships.iter().fold(0, |acc, r| acc + r.count),
+                r.pilots.iter().fold(0, |acc, r| acc + r.count),
+                r.upgrades.iter().fold(0, |acc, r| acc + r.count),
+            );
+        }
+    }
+
+    match format {
+        Format::Json => {
+            let path = output.unwrap_or(Path::new("trades.json"));
+            let buffer = serde_json::to_vec(&lists).map_err(Error::Json)?;
+            write_all(path, &buffer)?;
+            written(path);
+        }
+        Format::Csv => {
+            let dir = output_dir(output)?;
+            for (name, r#type) in [
+                ("trade_ships.csv", ItemType::Ship),
+                ("trade_pilots.csv", ItemType::Pilot),
+                ("trade_upgrades.csv", ItemType::Upgrade),
+            ] {
+                let path = dir.join(name);
+                lists.write_csv(r#type, create(&path)?)?;
+                written(&path);
+            }
+        }
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite => {
+            eprintln!("sqlite isn't supported for trade lists");
+            exit(1)
+        }
+        Format::Xlsx => {
+            let path = output.unwrap_or(Path::new("XWingTMG2_Trades.xlsx"));
+            write_all(path, &lists.workbook()?.save_to_buffer()?)?;
+            written(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Opens `path` for writing, or stdout for `-`.
+fn create(path: &Path) -> Result<Box<dyn Write>, Error> {
+    if is_stdout(path) {
+        return Ok(Box::new(io::stdout().lock()));
+    }
+    let f = File::create(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    Ok(Box::new(BufWriter::new(f)))
+}
+
+/// Writes the whole `buffer` to `path`, or stdout for `-`.
+fn write_all(path: &Path, buffer: &[u8]) -> Result<(), Error> {
+    let mut w = create(path)?;
+    w.write_all(buffer)
+        .and_then(|_| w.flush())
+        .map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+/// The directory for the formats that write several files, which can't go
+/// to stdout.
+fn output_dir(output: Option<&Path>) -> Result<&Path, Error> {
+    match output {
+        Some(p) if is_stdout(p) => Err(Error::Io {
+            path: p.to_owned(),
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "several csv files can't be written to stdout",
+            ),
+        }),
+        Some(p) => Ok(p),
+        None => Ok(Path::new(".")),
+    }
+}
+
+fn written(path: &Path) {
+    if !is_stdout(path) {
+        eprintln!("{} written", path.display());
+    }
+}
+
+/// Writes the inventory in the requested format to `--output`, or a file
+/// with the default name in the current directory.
+fn write_inventory(
+    args: &Args,
+    records: &Records,
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+) -> Result<(), Error> {
+    let output = args.output.as_deref();
+    match args.format {
+        Format::Json => {
+            let path = output.unwrap_or(Path::new("inventory.json"));
+            let buffer = serde_json::to_vec(records).map_err(Error::Json)?;
+            write_all(path, &buffer)?;
+            written(path);
+        }
+        Format::Csv if args.long => {
+            let path = output.unwrap_or(Path::new("inventory.csv"));
+            csv_export::write_long(records, create(path)?)?;
+            written(path);
+        }
+        Format::Csv => {
+            let dir = output_dir(output)?;
+            let path = dir.join("ships.csv");
+            csv_export::write(&records.ships, create(&path)?)?;
+            written(&path);
+            let path = dir.join("pilots.csv");
+            csv_export::write(&records.pilots, create(&path)?)?;
+            written(&path);
+            let path = dir.join("upgrades.csv");
+            csv_export::write(&records.upgrades, create(&path)?)?;
+            written(&path);
+            let path = dir.join("obstacles.csv");
+            csv_export::write(&records.obstacles, create(&path)?)?;
+            written(&path);
+            let path = dir.join("damage_decks.csv");
+            csv_export::write(&records.damage_decks, create(&path)?)?;
+            written(&path);
+            let path = dir.join("components.csv");
+            csv_export::write(&records.components, create(&path)?)?;
+            written(&path);
+        }
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite => {
+            let path = output.unwrap_or(Path::new("inventory.sqlite"));
+            if is_stdout(path) {
+                return Err(Error::Io {
+                    path: path.to_owned(),
+                    source: io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "a sqlite database can't be written to stdout",
+                    ),
+                });
+            }
+            xwingtmg2_inventory_rs::sqlite::write(path, catalog, data, inventory)?;
+            written(path);
+        }
+        Format::Xlsx => {
+            let path = output.unwrap_or(Path::new("XWingTMG2_Inventory.xlsx"));
+            let buffer = xwingtmg2_inventory_rs::generate_xls_to_buffer(
+                catalog,
+                data,
+                collection,
+                inventory,
+                &SheetOptions {
+                    only_owned: args.only_owned,
+                    dials: args.dials,
+                    upgrade_sides: args.upgrade_sides,
+                    availability: args.availability,
+                },
+            )?;
+            write_all(path, &buffer)?;
+            written(path);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the expansions to buy to cover everything asked for in `args`.
+fn plan_purchases(
+    args: &PlanArgs,
+    catalog: &Catalog,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<(), Error> {
+    let mut demand = match &args.faction {
+        Some(f) => plan::faction_demand(data, f),
+        None => Inventory::new(),
+    };
+    for (item, count) in &args.items {
+        *demand.entry(item.clone()).or_insert(0) += count;
+    }
+    for p in &args.squads {
+        for (item, count) in Squad::load(p)?.demand(data, squad::tracks_dials(inventory))? {
+            *demand.entry(item).or_insert(0) += count;
+        }
+    }
+
+    let options = plan::Options {
+        prices: args.prices.as_deref().map(plan::load_prices).transpose()?,
+        exclude: args.exclude.iter().cloned().collect(),
+    };
+    let plan = plan::plan(catalog, &plan::missing(&demand, inventory), &options);
+
+    for (sku, count) in &plan.purchases {
+        println!("buy {} x {} ({})", count, catalog.expansions[sku].name, sku);
+    }
+    match options.prices {
+        Some(_) => println!("total cost: {:.2}", plan.cost),
+        None => println!("total: {} expansions", plan.cost),
+    }
+    if !plan.uncovered.is_empty() {
+        println!("Not available in any expansion:");
+        for (item, count) in &plan.uncovered {
+            println!("- {:?} {} ({})", item.r#type, item.xws, count);
+        }
+    }
+    Ok(())
+}
+
 fn main() {
     let args = match parse_args() {
         Ok(v) => v,
@@ -72,67 +919,204 @@ fn main() {
         }
     };
 
-    let data = match Data::load_from_manifest(Path::new("xwing-data2")) {
+    let data = match load_data(args.data_dir.clone()) {
         Ok(d) => d,
         Err(e) => {
-            println!("{:?}", e);
+            eprintln!("{}", e);
             exit(1)
         }
     };
     //println!("{:?}", xws_data);
 
-    let catalog = match Catalog::load() {
+    let catalog = match load_catalog(args.catalog_json.clone()) {
         Ok(e) => e,
         Err(e) => {
-            println!("{:?}", e);
+            eprintln!("{}", e);
             exit(2)
         }
     };
 
-    let yasb_coll = match args.collection_json {
-        None => yasb2::Collection::default(),
-        Some(p) => match yasb2::Collection::load(&p) {
-            Ok(c) => c,
-            Err(e) => {
-                println!("{:?}", e);
+    if matches!(
+        args.command,
+        Command::AddExpansion(..)
+            | Command::AddSingle(..)
+            | Command::RemoveExpansion(..)
+            | Command::RemoveSingle(..)
+    ) {
+        let result = match &args.collection {
+            Some(p) => edit_collection(args.command, p, &catalog, &data),
+            None => {
+                eprintln!("a --collection file to edit is required");
                 exit(1)
             }
-        },
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            exit(1)
+        }
+        return;
+    }
+
+    let names = match load_yasb_names(args.yasb_names.clone()) {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2)
+        }
     };
 
-    let (mut skus, missing) = yasb_coll.expansion_skus(&catalog);
+    let compatibility = match load_compatibility(args.compatibility_json.clone()) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2)
+        }
+    };
+    let yasb = YasbImport {
+        names: &names,
+        data: &data,
+        compatibility: &compatibility,
+    };
 
-    println!("Not found expansions (probably 1.0, but for debugging):");
-    for n in missing {
-        println!("- {}", n);
+    if let Command::Diff(a, b) = &args.command {
+        if let Err(e) = diff_collections(a, b, &catalog, &yasb, args.format == Format::Json) {
+            eprintln!("{}", e);
+            exit(1)
+        }
+        return;
     }
 
-    if !args.only_owned {
-        for sku in catalog.expansions.keys() {
-            if skus.get(sku).is_none() {
-                skus.insert(sku.to_owned(), 0);
+    if let Command::YasbUnmapped = &args.command {
+        let result = match &args.collection {
+            Some(p) => print_unmapped(p, &yasb, args.format == Format::Json),
+            None => {
+                eprintln!("a YASB --collection is required");
+                exit(1)
             }
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            exit(1)
         }
+        return;
     }
 
-    let collection = Collection {
-        skus,
-        singles: yasb_coll.singles_as_xws(),
+    let mut collection = match &args.collection {
+        None => Collection::default(),
+        Some(p) => match load_collection(p, &catalog, &yasb) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        },
     };
 
-    let (inventory, missing) = collection.inventory(&catalog);
+    if !args.only_owned {
+        for sku in catalog.expansions.keys() {
+            if !collection.skus.contains_key(sku) {
+                collection.skus.insert(sku.to_owned(), 0);
+            }
+        }
+    }
+
+    // so the legacy models show up in the spreadsheet `Singles` as the current
+    // ships, which leaves nothing for the inventory to replace
+    collection.singles = compatibility.apply(collection.singles);
+    let (inventory, missing) = collection.inventory(&catalog, &compatibility);
     if !missing.is_empty() {
-        println!("YASB module added a not found expansion without reporting:");
+        eprintln!("YASB module added a not found expansion without reporting:");
         for n in missing {
-            println!("- {}", n);
+            eprintln!("- {}", n);
+        }
+    }
+
+    if let Command::Value(limit) = &args.command {
+        let values = value::build(&catalog, &data, &collection, &inventory);
+        for v in values.iter().take(limit.unwrap_or(values.len())) {
+            println!(
+                "{} ({}, {}): {} new ({} ships, {} pilots, {} upgrades), {} new components, {} duplicates {}",
+                v.name,
+                v.sku,
+                v.kind.as_ref(),
+                v.new_unique(),
+                v.new_ships,
+                v.new_pilots,
+                v.new_upgrades,
+                v.new_components,
+                v.duplicates.len(),
+                value::format_factions(&v.factions)
+            );
         }
+        return;
+    }
+
+    if let Command::Gaps = &args.command {
+        if let Err(e) = print_gaps(&inventory, &data, args.format == Format::Json) {
+            eprintln!("{}", e);
+            exit(1)
+        }
+        return;
+    }
+
+    if let Command::Usable(pilot) = &args.command {
+        if let Err(e) = print_usable(pilot, &inventory, &data, args.format == Format::Json) {
+            eprintln!("{}", e);
+            exit(1)
+        }
+        return;
+    }
+
+    if let Command::Plan(plan_args) = &args.command {
+        if let Err(e) = plan_purchases(plan_args, &catalog, &data, &inventory) {
+            eprintln!("{}", e);
+            exit(1)
+        }
+        return;
+    }
+
+    if let Command::Trade(trade_args) = &args.command {
+        if let Err(e) = write_trades(
+            trade_args,
+            &args.format,
+            args.output.as_deref(),
+            &catalog,
+            &yasb,
+            &data,
+            &inventory,
+        ) {
+            eprintln!("{}", e);
+            exit(1)
+        }
+        return;
+    }
+
+    if let Command::Check(squads, mode) = &args.command {
+        match check_squads(squads, *mode, &data, &inventory) {
+            Ok(true) => println!("buildable"),
+            Ok(false) => println!("not buildable"),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        }
+        return;
     }
 
     // TODO: Can some this to_owned() just be references?
     // FIXME: This is doing a bunch of stuff twice for xlsx generatino, but
     // the stats are nice, so keeping it for now.
-    let records = Records::build(&inventory, &data, &catalog);
-    println!(
+    let (mut records, errors) = Records::build(&inventory, &data, &catalog);
+    if args.upgrade_sides {
+        records.split_upgrade_sides(&data, &catalog);
+    }
+    if !args.dials {
+        records.clear_dials();
+    }
+    for e in errors {
+        eprintln!("{}", e);
+    }
+    eprintln!(
         "Total {} ships, {}/{} unique",
         records.ships.iter().fold(0, |acc, r| acc + r.count),
         records
@@ -141,7 +1125,7 @@ fn main() {
             .fold(0, |acc, r| if r.count > 0 { acc + 1 } else { acc }),
         records.ships.len(),
     );
-    println!(
+    eprintln!(
         "Total {} cards, {}/{} unique",
         records.pilots.iter().fold(0, |acc, r| acc + r.count),
         records
@@ -150,7 +1134,7 @@ fn main() {
             .fold(0, |acc, r| if r.count == 1 { acc + 1 } else { acc }),
         records.pilots.len(),
     );
-    println!(
+    eprintln!(
         "Total {} upgrades, {}/{} unique",
         records.upgrades.iter().fold(0, |acc, r| acc + r.count),
         records
@@ -159,26 +1143,28 @@ fn main() {
             .fold(0, |acc, r| if r.count > 0 { acc + 1 } else { acc }),
         records.upgrades.len(),
     );
+    let missing_dials: u32 = records
+        .components
+        .iter()
+        .filter(|c| c.r#type == ItemType::Dial)
+        .map(|c| c.ships.saturating_sub(c.count))
+        .sum();
+    if missing_dials > 0 {
+        eprintln!("Missing {} dials", missing_dials);
+    }
+    let gaps = gaps::build(&inventory, &data)
+        .iter()
+        .filter(|g| g.is_gap())
+        .count();
+    if gaps > 0 {
+        eprintln!(
+            "{} ships with models or pilot cards that can't be fielded, see gaps",
+            gaps
+        );
+    }
 
-    match args.format {
-        Format::Json => {
-            let f = File::create("inventory.json").unwrap();
-            match serde_json::to_writer(f, &records) {
-                Ok(_) => println!("inventory.json written"),
-                Err(err) => println!("inventory.json error: {}", err),
-            }
-        }
-        Format::Xlsx => {
-            match xwingtmg2_inventory_rs::generate_xls(
-                &catalog,
-                &data,
-                &collection,
-                &inventory,
-                args.only_owned,
-            ) {
-                Ok(_) => println!("xlsx written"),
-                Err(err) => println!("xlsx error: {}", err),
-            }
-        }
-    };
+    if let Err(err) = write_inventory(&args, &records, &catalog, &data, &collection, &inventory) {
+        eprintln!("{}", err);
+        exit(1)
+    }
 }
@@ -0,0 +1,304 @@
+//! Answers "How do I get X if I don't have it?" by picking expansions to buy.
+//!
+//! Finding the truly smallest or cheapest set of expansions is a set cover
+//! problem, so this uses the usual greedy approximation: repeatedly buy the
+//! expansion that covers the most missing items per unit of cost, then drop
+//! any purchases that turned out to be redundant. It is not guaranteed to be
+//! optimal, but it is fast and close enough to decide what to shop for.
+//!
+//! Without a price table every expansion costs 1, so the plan minimizes the
+//! number of expansions bought. Placeholders, like `swzunreleased`, are never
+//! bought.
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::expansions::{Catalog, Item, ItemType, SKU};
+use crate::xwingdata2::Data;
+use crate::{Error, Inventory};
+
+/// A price per SKU, loaded from a json or toml map, e.g. `swz25 = 19.99`.
+pub type Prices = BTreeMap<SKU, f64>;
+
+pub fn load_prices(path: &Path) -> Result<Prices, Error> {
+    crate::from_json_or_toml(path)
+}
+
+/// Which expansions can be bought.
+#[derive(Default, Debug)]
+pub struct Options {
+    /// When set, only the SKUs with a price are considered, so out of stock
+    /// expansions can simply be left out of the table.
+    pub prices: Option<Prices>,
+    /// SKUs that can't be bought, e.g. because they are out of print.
+    pub exclude: BTreeSet<SKU>,
+}
+
+impl Options {
+    fn cost(&self, catalog: &Catalog, sku: &str) -> Option<f64> {
+        if self.exclude.contains(sku) || !catalog.expansions[sku].kind.is_product() {
+            return None;
+        }
+        match &self.prices {
+            None => Some(1.0),
+            Some(p) => p.get(sku).copied(),
+        }
+    }
+}
+
+/// The expansions to buy to cover a demand.
+#[derive(Default, Debug, PartialEq)]
+pub struct Plan {
+    /// The SKUs to buy and how many of each.
+    pub purchases: BTreeMap<SKU, u32>,
+    /// The total cost, or just the number of expansions without prices.
+    pub cost: f64,
+    /// Items still missing after the purchases, because no available
+    /// expansion contains them.
+    pub uncovered: Inventory,
+}
+
+/// The items in `demand` that aren't in the inventory in the numbers needed.
+pub fn missing(demand: &Inventory, inventory: &Inventory) -> Inventory {
+    demand
+        .iter()
+        .filter_map(|(item, needed)| {
+            let owned = *inventory.get(item).unwrap_or(&0);
+            (owned < *needed).then(|| (item.clone(), needed - owned))
+        })
+        .collect()
+}
+
+/// One of every pilot and ship model in the faction, and every upgrade that
+/// is restricted to it.
+pub fn faction_demand(data: &Data, faction: &str) -> Inventory {
+    let mut demand = Inventory::new();
+    for ship in data.ships.iter().filter(|s| s.faction == faction) {
+        demand.insert(
+            Item {
+                r#type: ItemType::Ship,
+                xws: ship.xws.to_owned(),
+            },
+            1,
+        );
+        for pilot in &ship.pilots {
+            demand.insert(
+                Item {
+                    r#type: ItemType::Pilot,
+                    xws: pilot.xws.to_owned(),
+                },
+                1,
+            );
+        }
+    }
+    for upgrade in &data.upgrades {
+        if upgrade
+            .restrictions
+            .iter()
+            .any(|r| r.factions.iter().any(|f| f == faction))
+        {
+            demand.insert(
+                Item {
+                    r#type: ItemType::Upgrade,
+                    xws: upgrade.xws.to_owned(),
+                },
+                1,
+            );
+        }
+    }
+    demand
+}
+
+/// The number of still `needed` items that buying `sku` would cover.
+fn covered(catalog: &Catalog, sku: &str, needed: &Inventory) -> u32 {
+    catalog.expansions[sku]
+        .contents
+        .iter()
+        .map(|c| c.count.min(*needed.get(&c.item).unwrap_or(&0)))
+        .sum()
+}
+
+fn subtract(catalog: &Catalog, sku: &str, needed: &mut Inventory) {
+    for c in &catalog.expansions[sku].contents {
+        if let Some(n) = needed.get_mut(&c.item) {
+            *n = n.saturating_sub(c.count);
+            if *n == 0 {
+                needed.remove(&c.item);
+            }
+        }
+    }
+}
+
+/// Plans the purchases to cover the `missing` items, see [`missing`].
+pub fn plan(catalog: &Catalog, missing: &Inventory, options: &Options) -> Plan {
+    let mut needed = missing.clone();
+    let mut picks: Vec<(SKU, f64)> = vec![];
+
+    let candidates: Vec<(&SKU, f64)> = catalog
+        .expansions
+        .keys()
+        .filter_map(|sku| options.cost(catalog, sku).map(|c| (sku, c)))
+        .collect();
+
+    while !needed.is_empty() {
+        let mut best: Option<(&SKU, f64, f64)> = None;
+        for (sku, cost) in &candidates {
+            let n = covered(catalog, sku, &needed);
+            if n == 0 {
+                continue;
+            }
+            // free expansions are always worth it
+            let value = if *cost > 0.0 {
+                n as f64 / cost
+            } else {
+                f64::INFINITY
+            };
+            if best.is_none_or(|(_, _, v)| value > v) {
+                best = Some((sku, *cost, value));
+            }
+        }
+
+        match best {
+            None => break,
+            Some((sku, cost, _)) => {
+                subtract(catalog, sku, &mut needed);
+                picks.push((sku.to_owned(), cost));
+            }
+        }
+    }
+
+    // Greedy picks made early can be made redundant by later ones, so try
+    // dropping the most expensive ones first.
+    let mut order: Vec<usize> = (0..picks.len()).collect();
+    order.sort_by(|a, b| picks[*b].1.total_cmp(&picks[*a].1));
+    let mut kept = vec![true; picks.len()];
+    for i in order {
+        kept[i] = false;
+        let mut check = missing.clone();
+        for (j, (sku, _)) in picks.iter().enumerate() {
+            if kept[j] {
+                subtract(catalog, sku, &mut check);
+            }
+        }
+        if check.values().sum::<u32>() > needed.values().sum() {
+            kept[i] = true;
+        }
+    }
+
+    let mut plan = Plan {
+        uncovered: needed,
+        ..Default::default()
+    };
+    for (i, (sku, cost)) in picks.into_iter().enumerate() {
+        if kept[i] {
+            *plan.purchases.entry(sku).or_insert(0) += 1;
+            plan.cost += cost;
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        Catalog::from_json(
+            Path::new("test.json"),
+            r#"[
+                {"sku": "core", "name": "Core", "wave": 0, "contents": [
+                    {"type": "ship", "xws": "t65xwing", "count": 1},
+                    {"type": "ship", "xws": "tielnfighter", "count": 2},
+                    {"type": "upgrade", "xws": "predator", "count": 1}
+                ]},
+                {"sku": "xwing", "name": "X-Wing", "wave": 1, "contents": [
+                    {"type": "ship", "xws": "t65xwing", "count": 1}
+                ]},
+                {"sku": "tie", "name": "TIE", "wave": 1, "contents": [
+                    {"type": "ship", "xws": "tielnfighter", "count": 1}
+                ]},
+                {"sku": "unreleased", "name": "Unreleased", "wave": 99, "kind": "placeholder",
+                 "contents": [
+                    {"type": "ship", "xws": "t65xwing", "count": 1},
+                    {"type": "ship", "xws": "tielnfighter", "count": 1},
+                    {"type": "ship", "xws": "ewing", "count": 1}
+                ]}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_fewest() {
+        let mut demand = Inventory::new();
+        demand.insert(Item::new(ItemType::Ship, "t65xwing"), 1);
+        demand.insert(Item::new(ItemType::Ship, "tielnfighter"), 2);
+        demand.insert(Item::new(ItemType::Ship, "yt1300"), 1);
+
+        let plan = plan(&catalog(), &demand, &Options::default());
+        assert_eq!(plan.purchases, BTreeMap::from([("core".to_owned(), 1)]));
+        assert_eq!(plan.cost, 1.0);
+        assert_eq!(
+            plan.uncovered,
+            Inventory::from([(Item::new(ItemType::Ship, "yt1300"), 1)])
+        );
+    }
+
+    #[test]
+    fn test_plan_cheapest() {
+        let mut demand = Inventory::new();
+        demand.insert(Item::new(ItemType::Ship, "t65xwing"), 1);
+        demand.insert(Item::new(ItemType::Ship, "tielnfighter"), 2);
+
+        let options = Options {
+            prices: Some(Prices::from([
+                ("core".to_owned(), 50.0),
+                ("xwing".to_owned(), 10.0),
+                ("tie".to_owned(), 10.0),
+            ])),
+            ..Default::default()
+        };
+        let plan = plan(&catalog(), &demand, &options);
+        assert_eq!(
+            plan.purchases,
+            BTreeMap::from([("tie".to_owned(), 2), ("xwing".to_owned(), 1)])
+        );
+        assert_eq!(plan.cost, 30.0);
+    }
+
+    #[test]
+    fn test_plan_exclude() {
+        let demand = Inventory::from([(Item::new(ItemType::Upgrade, "predator"), 1)]);
+
+        let options = Options {
+            exclude: BTreeSet::from(["core".to_owned()]),
+            ..Default::default()
+        };
+        let plan = plan(&catalog(), &demand, &options);
+        assert!(plan.purchases.is_empty());
+        assert_eq!(plan.uncovered, demand);
+    }
+
+    #[test]
+    fn test_plan_placeholder() {
+        let demand = Inventory::from([
+            (Item::new(ItemType::Ship, "t65xwing"), 1),
+            (Item::new(ItemType::Ship, "tielnfighter"), 1),
+            (Item::new(ItemType::Ship, "ewing"), 1),
+        ]);
+
+        // it would cover everything for free, but can't be bought
+        let options = Options {
+            prices: Some(Prices::from([
+                ("unreleased".to_owned(), 0.0),
+                ("core".to_owned(), 50.0),
+            ])),
+            ..Default::default()
+        };
+        let plan = plan(&catalog(), &demand, &options);
+        assert_eq!(plan.purchases, BTreeMap::from([("core".to_owned(), 1)]));
+        assert_eq!(
+            plan.uncovered,
+            Inventory::from([(Item::new(ItemType::Ship, "ewing"), 1)])
+        );
+    }
+}
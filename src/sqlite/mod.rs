@@ -0,0 +1,296 @@
+//! Writes the catalog, xwing-data2 and an inventory to a sqlite database, so
+//! they can be queried with SQL instead of jq.
+//!
+//! The tables are normalized, with the xws ids and SKUs as the keys:
+//!
+//! - `factions(xws, name)`
+//! - `expansions(sku, name, wave, kind)`
+//! - `expansion_contents(sku, type, xws, count)`
+//! - `ships(xws, faction, name, size)`, one row per faction a ship is in
+//! - `pilots(xws, ship, faction, name, caption, initiative, limited, standard_loadout)`
+//! - `upgrades(xws, name, limited)`
+//! - `upgrade_sides(upgrade, side, type, slot)`, one row per slot of a side
+//! - `restrictions(upgrade, restriction, kind, value)`, one row per value.
+//!   The upgrade is usable if every `restriction` number of the upgrade has
+//!   at least one matching value.
+//! - `inventory(type, xws, count)`
+//!
+//! For example, all the Crew upgrades usable by Scum that aren't owned:
+//!
+//! ```sql
+//! SELECT u.name FROM upgrades u
+//! JOIN upgrade_sides s ON s.upgrade = u.xws AND s.side = 0 AND s.type = 'Crew'
+//! LEFT JOIN inventory i ON i.type = 'upgrade' AND i.xws = u.xws
+//! WHERE coalesce(i.count, 0) = 0
+//! AND NOT EXISTS (
+//!   SELECT 1 FROM restrictions r WHERE r.upgrade = u.xws AND r.kind = 'factions'
+//!   GROUP BY r.restriction HAVING sum(r.value = 'scumandvillainy') = 0
+//! );
+//! ```
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+use crate::expansions::Catalog;
+use crate::xwingdata2::{Data, Restrictions};
+use crate::{Error, Inventory};
+
+const SCHEMA: &str = "
+CREATE TABLE factions (
+    xws TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+CREATE TABLE expansions (
+    sku TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    wave INTEGER NOT NULL,
+    kind TEXT NOT NULL
+);
+CREATE TABLE expansion_contents (
+    sku TEXT NOT NULL REFERENCES expansions(sku),
+    type TEXT NOT NULL,
+    xws TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (sku, type, xws)
+);
+CREATE TABLE ships (
+    xws TEXT NOT NULL,
+    faction TEXT NOT NULL REFERENCES factions(xws),
+    name TEXT NOT NULL,
+    size TEXT NOT NULL,
+    PRIMARY KEY (xws, faction)
+);
+CREATE TABLE pilots (
+    xws TEXT PRIMARY KEY,
+    ship TEXT NOT NULL,
+    faction TEXT NOT NULL REFERENCES factions(xws),
+    name TEXT NOT NULL,
+    caption TEXT,
+    initiative INTEGER NOT NULL,
+    limited INTEGER NOT NULL,
+    standard_loadout INTEGER NOT NULL
+);
+CREATE TABLE upgrades (
+    xws TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    limited INTEGER NOT NULL
+);
+CREATE TABLE upgrade_sides (
+    upgrade TEXT NOT NULL REFERENCES upgrades(xws),
+    side INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    slot TEXT NOT NULL
+);
+CREATE TABLE restrictions (
+    upgrade TEXT NOT NULL REFERENCES upgrades(xws),
+    restriction INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE inventory (
+    type TEXT NOT NULL,
+    xws TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (type, xws)
+);
+";
+
+/// Writes a new database, replacing any existing file.
+pub fn write(
+    path: &Path,
+    catalog: &Catalog,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<(), Error> {
+    if path.exists() {
+        std::fs::remove_file(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+    }
+    let mut conn = Connection::open(path)?;
+    populate(&mut conn, catalog, data, inventory)
+}
+
+/// Creates and fills in the tables in an empty database.
+pub fn populate(
+    conn: &mut Connection,
+    catalog: &Catalog,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<(), Error> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+
+    {
+        let mut insert = tx.prepare("INSERT INTO factions VALUES (?1, ?2)")?;
+        for f in &data.factions {
+            insert.execute(params![f.xws, f.name])?;
+        }
+
+        let mut insert = tx.prepare("INSERT INTO expansions VALUES (?1, ?2, ?3, ?4)")?;
+        // some expansions list the same item more than once
+        let mut insert_content = tx.prepare(
+            "INSERT INTO expansion_contents VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET count = count + excluded.count",
+        )?;
+        for e in catalog.expansions.values() {
+            insert.execute(params![e.sku, e.name, e.wave, e.kind.as_ref()])?;
+            for c in &e.contents {
+                insert_content.execute(params![
+                    e.sku,
+                    c.item.r#type.as_ref(),
+                    c.item.xws,
+                    c.count
+                ])?;
+            }
+        }
+
+        let mut insert_ship = tx.prepare("INSERT INTO ships VALUES (?1, ?2, ?3, ?4)")?;
+        let mut insert_pilot =
+            tx.prepare("INSERT INTO pilots VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+        for s in &data.ships {
+            insert_ship.execute(params![s.xws, s.faction, s.name, s.size])?;
+            for p in &s.pilots {
+                insert_pilot.execute(params![
+                    p.xws,
+                    s.xws,
+                    s.faction,
+                    p.name,
+                    p.caption,
+                    p.initiative,
+                    p.limited,
+                    p.standard_loadout.as_ref().is_some_and(|l| !l.is_empty()),
+                ])?;
+            }
+        }
+
+        let mut insert = tx.prepare("INSERT INTO upgrades VALUES (?1, ?2, ?3)")?;
+        let mut insert_side = tx.prepare("INSERT INTO upgrade_sides VALUES (?1, ?2, ?3, ?4)")?;
+        let mut insert_restriction =
+            tx.prepare("INSERT INTO restrictions VALUES (?1, ?2, ?3, ?4)")?;
+        for u in &data.upgrades {
+            insert.execute(params![u.xws, u.name, u.limited])?;
+            for (i, side) in u.sides.iter().enumerate() {
+                for slot in &side.slots {
+                    insert_side.execute(params![
+                        u.xws,
+                        i,
+                        format!("{:?}", side.r#type),
+                        format!("{:?}", slot)
+                    ])?;
+                }
+            }
+            for (i, r) in u.restrictions.iter().enumerate() {
+                for (kind, value) in restriction_values(r) {
+                    insert_restriction.execute(params![u.xws, i, kind, value])?;
+                }
+            }
+        }
+
+        let mut insert = tx.prepare("INSERT INTO inventory VALUES (?1, ?2, ?3)")?;
+        for (item, count) in inventory {
+            insert.execute(params![item.r#type.as_ref(), item.xws, count])?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// The `(kind, value)` pairs of a restriction, named like the xwing-data2
+/// fields.
+fn restriction_values(r: &Restrictions) -> Vec<(&'static str, String)> {
+    let mut values = vec![];
+    for (kind, list) in [
+        ("factions", &r.factions),
+        ("sizes", &r.sizes),
+        ("ships", &r.ships),
+        ("arcs", &r.arcs),
+        ("keywords", &r.keywords),
+        ("force_side", &r.force_side),
+        ("equipped", &r.equipped),
+    ] {
+        values.extend(list.iter().map(|v| (kind, v.to_owned())));
+    }
+    if let Some(a) = &r.action {
+        let value = match &a.difficulty {
+            Some(d) => format!("{} {}", d, a.r#type),
+            None => a.r#type.to_owned(),
+        };
+        values.push(("action", value));
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::expansions::{Item, ItemType};
+    use crate::xwingdata2::{fixtures, Faction, SlotKind, Upgrade};
+
+    const UNOWNED_SCUM_CREW: &str = "
+        SELECT u.name FROM upgrades u
+        JOIN upgrade_sides s ON s.upgrade = u.xws AND s.side = 0 AND s.type = 'Crew'
+        LEFT JOIN inventory i ON i.type = 'upgrade' AND i.xws = u.xws
+        WHERE coalesce(i.count, 0) = 0
+        AND NOT EXISTS (
+          SELECT 1 FROM restrictions r WHERE r.upgrade = u.xws AND r.kind = 'factions'
+          GROUP BY r.restriction HAVING sum(r.value = 'scumandvillainy') = 0
+        )";
+
+    #[test]
+    fn test_populate() {
+        let catalog = Catalog::from_json(
+            Path::new("test.json"),
+            r#"[{"sku": "swz82", "name": "Scum Crew", "wave": 1, "contents": [
+                {"type": "upgrade", "xws": "4lom", "count": 1},
+                {"type": "upgrade", "xws": "chewbacca", "count": 1}
+            ]}]"#,
+        )
+        .unwrap();
+        let upgrade = |xws: &str, factions: Vec<String>| Upgrade {
+            restrictions: vec![Restrictions {
+                factions,
+                ..Default::default()
+            }],
+            ..fixtures::upgrade(xws, SlotKind::Crew)
+        };
+        let data = Data {
+            ships: vec![],
+            upgrades: vec![
+                upgrade("4lom", vec!["scumandvillainy".to_owned()]),
+                upgrade("chewbacca", vec!["rebelalliance".to_owned()]),
+                upgrade("hondo", vec![]),
+            ],
+            factions: vec![Faction {
+                xws: "scumandvillainy".to_owned(),
+                name: "Scum and Villainy".to_owned(),
+            }],
+            ..Default::default()
+        };
+        let inventory = Inventory::from([(
+            Item {
+                r#type: ItemType::Upgrade,
+                xws: "4lom".to_owned(),
+            },
+            1,
+        )]);
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        populate(&mut conn, &catalog, &data, &inventory).unwrap();
+
+        let count: u32 = conn
+            .query_row("SELECT count(*) FROM expansion_contents", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+
+        // the example from the module docs
+        let mut stmt = conn.prepare(UNOWNED_SCUM_CREW).unwrap();
+        let names: Vec<String> = stmt
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(names, vec!["hondo"]);
+    }
+}
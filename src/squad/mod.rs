@@ -0,0 +1,339 @@
+//! Checks whether a squad in the standard XWS json format can be built from
+//! a collection.
+//!
+//! Every pilot needs its card, a ship model and a dial, and every upgrade
+//! needs its own card, so an upgrade used by two pilots needs two copies.
+//! Dials are only needed from collections that track them, see
+//! [`tracks_dials`], since YASB singles never have any.
+//! Pilots with a standard loadout have their upgrades printed on the pilot
+//! card, so those don't count.
+//!
+//! Several squads can be checked together with a [`Report`], either all
+//! fielded at the same time (e.g. doubles sharing a collection) or one at a
+//! time (e.g. a league registering several lists).
+//!
+//! ```json
+//! {
+//!   "faction": "rebelalliance",
+//!   "pilots": [
+//!     {
+//!       "id": "lukeskywalker",
+//!       "ship": "t65xwing",
+//!       "upgrades": { "talent": ["predator"], "astromech": ["r2d2"] }
+//!     }
+//!   ]
+//! }
+//! ```
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use strum::EnumString;
+
+use crate::expansions::{Item, ItemType, XWS};
+use crate::xwingdata2::Data;
+use crate::{Error, Inventory};
+
+/// The parts of an XWS squad needed to know what it is made of.
+#[derive(Deserialize, Debug)]
+pub struct Squad {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub faction: String,
+    pub pilots: Vec<SquadPilot>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SquadPilot {
+    /// Older builders use `name` for the pilot xws.
+    #[serde(alias = "name")]
+    pub id: XWS,
+    #[serde(default)]
+    pub ship: Option<XWS>,
+    /// Upgrades xws ids keyed by the slot they are equipped in.
+    #[serde(default)]
+    pub upgrades: BTreeMap<String, Vec<XWS>>,
+}
+
+impl Squad {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let buffer = crate::read_to_string(path)?;
+        crate::from_json(path, &buffer)
+    }
+
+    /// The number of each pilot card, ship model, dial and upgrade card
+    /// needed to put the squad on the table, leaving out the dials unless
+    /// `dials` is set.
+    ///
+    /// The ship model is always the one xwing-data2 lists the pilot under,
+    /// since the `ship` in the XWS is optional.
+    pub fn demand(&self, data: &Data, dials: bool) -> Result<Inventory, Error> {
+        let mut demand = Inventory::new();
+
+        for p in &self.pilots {
+            let (ship, pilot) = data.get_pilot(&p.id).ok_or_else(|| {
+                Error::UnknownXws(Item {
+                    r#type: ItemType::Pilot,
+                    xws: p.id.to_owned(),
+                })
+            })?;
+
+            add(&mut demand, ItemType::Pilot, &pilot.xws);
+            add(&mut demand, ItemType::Ship, &ship.xws);
+            if dials {
+                add(&mut demand, ItemType::Dial, &ship.xws);
+            }
+
+            if pilot
+                .standard_loadout
+                .as_ref()
+                .is_some_and(|l| !l.is_empty())
+            {
+                continue;
+            }
+
+            for xws in p.upgrades.values().flatten() {
+                if data.get_upgrade(xws).is_none() {
+                    return Err(Error::UnknownXws(Item {
+                        r#type: ItemType::Upgrade,
+                        xws: xws.to_owned(),
+                    }));
+                }
+                add(&mut demand, ItemType::Upgrade, xws);
+            }
+        }
+
+        Ok(demand)
+    }
+}
+
+/// Whether the inventory has any dials, so squads should need them.
+pub fn tracks_dials(inventory: &Inventory) -> bool {
+    inventory
+        .iter()
+        .any(|(i, c)| i.r#type == ItemType::Dial && *c > 0)
+}
+
+fn add(inventory: &mut Inventory, r#type: ItemType, xws: &str) {
+    *inventory
+        .entry(Item {
+            r#type,
+            xws: xws.to_owned(),
+        })
+        .or_insert(0) += 1;
+}
+
+/// How several squads use a collection.
+#[derive(EnumString, Copy, Clone, PartialEq, Debug)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Mode {
+    /// All the squads are on the table at once, so the demands are summed.
+    Simultaneous,
+    /// Only one squad is on the table at a time, so only the largest demand
+    /// for each item matters.
+    Sequential,
+}
+
+/// The combined demand of several squads for a single item.
+#[derive(Debug, PartialEq)]
+pub struct ItemDemand {
+    pub item: Item,
+    /// The demand of each squad, in the order given.
+    pub per_squad: Vec<u32>,
+    pub needed: u32,
+    pub owned: u32,
+}
+
+/// The combined demand of several squads against an inventory.
+#[derive(Debug)]
+pub struct Report {
+    pub mode: Mode,
+    pub items: Vec<ItemDemand>,
+}
+
+impl Report {
+    pub fn build(demands: &[Inventory], mode: Mode, inventory: &Inventory) -> Report {
+        let mut per_item: BTreeMap<&Item, Vec<u32>> = BTreeMap::new();
+        for (i, demand) in demands.iter().enumerate() {
+            for (item, count) in demand {
+                per_item
+                    .entry(item)
+                    .or_insert_with(|| vec![0; demands.len()])[i] = *count;
+            }
+        }
+
+        let items = per_item
+            .into_iter()
+            .map(|(item, per_squad)| ItemDemand {
+                item: item.clone(),
+                needed: match mode {
+                    Mode::Simultaneous => per_squad.iter().sum(),
+                    Mode::Sequential => per_squad.iter().copied().max().unwrap_or(0),
+                },
+                owned: *inventory.get(item).unwrap_or(&0),
+                per_squad,
+            })
+            .collect();
+
+        Report { mode, items }
+    }
+
+    /// The items that aren't owned in the numbers needed.
+    pub fn shortfalls(&self) -> impl Iterator<Item = &ItemDemand> {
+        self.items.iter().filter(|d| d.owned < d.needed)
+    }
+
+    pub fn is_buildable(&self) -> bool {
+        self.shortfalls().next().is_none()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compatibility::Compatibility;
+    use crate::expansions::Catalog;
+    use crate::xwingdata2::fixtures::{pilot, ship, upgrade};
+    use crate::xwingdata2::{Pilot, SlotKind};
+    use crate::Collection;
+
+    fn data() -> Data {
+        Data {
+            ships: vec![ship(
+                "TIE/ln Fighter",
+                "tielnfighter",
+                "galacticempire",
+                vec![
+                    pilot("blacksquadronace"),
+                    Pilot {
+                        standard_loadout: Some(vec!["predator".to_owned()]),
+                        ..pilot("idenversio-battleofyavin")
+                    },
+                ],
+            )],
+            upgrades: vec![upgrade("predator", SlotKind::Talent)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_demand() {
+        let squad: Squad = serde_json::from_str(
+            r#"{"faction": "galacticempire", "pilots": [
+                {"id": "blacksquadronace", "ship": "tielnfighter", "upgrades": {"talent": ["predator"]}},
+                {"id": "blacksquadronace", "upgrades": {"talent": ["predator"]}},
+                {"id": "idenversio-battleofyavin", "upgrades": {"talent": ["predator"]}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let demand = squad.demand(&data(), true).unwrap();
+        assert_eq!(demand[&Item::new(ItemType::Ship, "tielnfighter")], 3);
+        assert_eq!(demand[&Item::new(ItemType::Dial, "tielnfighter")], 3);
+        assert_eq!(demand[&Item::new(ItemType::Pilot, "blacksquadronace")], 2);
+        // the standard loadout doesn't need the card
+        assert_eq!(demand[&Item::new(ItemType::Upgrade, "predator")], 2);
+
+        let mut inventory = Inventory::new();
+        inventory.insert(Item::new(ItemType::Ship, "tielnfighter"), 3);
+        inventory.insert(Item::new(ItemType::Dial, "tielnfighter"), 3);
+        inventory.insert(Item::new(ItemType::Pilot, "blacksquadronace"), 2);
+        inventory.insert(Item::new(ItemType::Pilot, "idenversio-battleofyavin"), 1);
+        inventory.insert(Item::new(ItemType::Upgrade, "predator"), 1);
+
+        let report = Report::build(&[demand], Mode::Simultaneous, &inventory);
+        assert_eq!(
+            report.shortfalls().collect::<Vec<_>>(),
+            vec![&ItemDemand {
+                item: Item::new(ItemType::Upgrade, "predator"),
+                per_squad: vec![2],
+                needed: 2,
+                owned: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_without_dials() {
+        let squad: Squad = serde_json::from_str(
+            r#"{"faction": "galacticempire", "pilots": [{"id": "blacksquadronace"}]}"#,
+        )
+        .unwrap();
+        let inventory = Inventory::from([
+            (Item::new(ItemType::Ship, "tielnfighter"), 1),
+            (Item::new(ItemType::Pilot, "blacksquadronace"), 1),
+            // from an expansion that isn't owned
+            (Item::new(ItemType::Dial, "t65xwing"), 0),
+        ]);
+
+        assert!(!tracks_dials(&inventory));
+        let demand = squad.demand(&data(), tracks_dials(&inventory)).unwrap();
+        assert!(!demand.contains_key(&Item::new(ItemType::Dial, "tielnfighter")));
+        let report = Report::build(&[demand], Mode::Simultaneous, &inventory);
+        assert!(report.is_buildable());
+    }
+
+    #[test]
+    fn test_report() {
+        let tie = Item::new(ItemType::Ship, "tielnfighter");
+        let predator = Item::new(ItemType::Upgrade, "predator");
+
+        let mut a = Inventory::new();
+        a.insert(tie.clone(), 2);
+        a.insert(predator.clone(), 1);
+        let mut b = Inventory::new();
+        b.insert(tie.clone(), 3);
+
+        let mut inventory = Inventory::new();
+        inventory.insert(tie.clone(), 3);
+        inventory.insert(predator.clone(), 1);
+
+        let demands = [a, b];
+        let report = Report::build(&demands, Mode::Simultaneous, &inventory);
+        assert_eq!(
+            report.shortfalls().collect::<Vec<_>>(),
+            vec![&ItemDemand {
+                item: tie.clone(),
+                per_squad: vec![2, 3],
+                needed: 5,
+                owned: 3,
+            }]
+        );
+
+        let report = Report::build(&demands, Mode::Sequential, &inventory);
+        assert!(report.is_buildable());
+        assert_eq!(report.items[0].per_squad, vec![2, 3]);
+        assert_eq!(report.items[0].needed, 3);
+    }
+
+    #[test]
+    fn test_legacy_model() {
+        let squad: Squad = serde_json::from_str(
+            r#"{"faction": "galacticempire", "pilots": [{"id": "blacksquadronace"}]}"#,
+        )
+        .unwrap();
+        let compatibility: Compatibility =
+            serde_json::from_str(r#"{"tiefighter-legacyyasb": ["tielnfighter"]}"#).unwrap();
+
+        let mut collection = Collection::default();
+        collection.add_single(Item::new(ItemType::Ship, "tiefighter-legacyyasb"), 1);
+        collection.add_single(Item::new(ItemType::Dial, "tielnfighter"), 1);
+        collection.add_single(Item::new(ItemType::Pilot, "blacksquadronace"), 1);
+        let (inventory, _) = collection.inventory(&Catalog::default(), &compatibility);
+
+        let demand = squad.demand(&data(), tracks_dials(&inventory)).unwrap();
+        let report = Report::build(&[demand], Mode::Simultaneous, &inventory);
+        assert!(report.is_buildable());
+    }
+
+    #[test]
+    fn test_unknown_pilot() {
+        let squad: Squad =
+            serde_json::from_str(r#"{"faction": "galacticempire", "pilots": [{"id": "nobody"}]}"#)
+                .unwrap();
+
+        match squad.demand(&data(), true) {
+            Err(Error::UnknownXws(i)) => assert_eq!(i, Item::new(ItemType::Pilot, "nobody")),
+            r => panic!("expected an unknown pilot, got {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Trade lists: the ships, pilots and upgrades beyond what a collector wants
+//! to keep, and what two collectors could swap with each other.
+//!
+//! How many of each item to keep is set per type, either as a fixed count or
+//! as enough copies to field the most a single list can use: the `limited`
+//! number for unique cards and [`LIST_MAX`] for everything else.
+//!
+//! The other collector is either another collection, in which case both
+//! sides' surplus is matched against what the other is short of with the
+//! same keep rules, or just a want list of items they are after.
+use rust_xlsxwriter::{Table, TableStyle, Workbook, XlsxError};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::expansions::{Catalog, Item, ItemType};
+use crate::xwingdata2::Data;
+use crate::{Error, Inventory, Records};
+
+/// The most ships in a standard list, so the most copies of any item that
+/// isn't limited that a single list can use.
+pub const LIST_MAX: u32 = 8;
+
+/// How many of an item to keep out of trades.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Keep {
+    Count(u32),
+    /// The most copies a single list can use.
+    ListMax,
+}
+
+/// Either a number or `list`.
+impl FromStr for Keep {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("list") {
+            return Ok(Keep::ListMax);
+        }
+        s.parse().map(Keep::Count)
+    }
+}
+
+#[derive(Debug)]
+pub struct KeepRules {
+    pub ships: Keep,
+    pub pilots: Keep,
+    pub upgrades: Keep,
+}
+
+impl Default for KeepRules {
+    fn default() -> Self {
+        KeepRules {
+            ships: Keep::Count(1),
+            pilots: Keep::Count(1),
+            upgrades: Keep::Count(1),
+        }
+    }
+}
+
+impl KeepRules {
+    /// The number of `item` to keep, or `None` for the types that aren't
+    /// traded.
+    pub fn keep(&self, item: &Item, data: &Data) -> Option<u32> {
+        let (keep, limited) = match item.r#type {
+            ItemType::Ship => (self.ships, 0),
+            ItemType::Pilot => (
+                self.pilots,
+                data.get_pilot(&item.xws).map_or(0, |(_, p)| p.limited),
+            ),
+            ItemType::Upgrade => (
+                self.upgrades,
+                data.get_upgrade(&item.xws).map_or(0, |u| u.limited),
+            ),
+            _ => return None,
+        };
+        Some(match keep {
+            Keep::Count(n) => n,
+            Keep::ListMax if limited > 0 => limited,
+            Keep::ListMax => LIST_MAX,
+        })
+    }
+
+    /// How many more of `item` the inventory needs to keep the full amount.
+    fn wanted(&self, inventory: &Inventory, item: &Item, data: &Data) -> u32 {
+        self.keep(item, data)
+            .map_or(0, |k| k.saturating_sub(*inventory.get(item).unwrap_or(&0)))
+    }
+}
+
+/// The items beyond the number to keep.
+pub fn surplus(inventory: &Inventory, data: &Data, rules: &KeepRules) -> Inventory {
+    inventory
+        .iter()
+        .filter_map(|(item, count)| {
+            let keep = rules.keep(item, data)?;
+            (*count > keep).then(|| (item.clone(), count - keep))
+        })
+        .collect()
+}
+
+/// The other side of a trade.
+#[derive(Debug)]
+pub enum Other {
+    /// The inventory of another collection.
+    Collection(Inventory),
+    /// Just the items wanted and how many of each.
+    Wants(Inventory),
+}
+
+/// The items that could be swapped with another collector.
+#[derive(Default, Debug, PartialEq)]
+pub struct Matches {
+    /// Surplus items that the other collector wants.
+    pub give: Inventory,
+    /// The other collection's surplus items that this one wants. Always
+    /// empty for a want list.
+    pub receive: Inventory,
+}
+
+pub fn matches(inventory: &Inventory, other: &Other, data: &Data, rules: &KeepRules) -> Matches {
+    let mut matches = Matches::default();
+
+    for (item, count) in surplus(inventory, data, rules) {
+        let wanted = match other {
+            Other::Collection(theirs) => rules.wanted(theirs, &item, data),
+            Other::Wants(wants) => *wants.get(&item).unwrap_or(&0),
+        };
+        if wanted > 0 {
+            matches.give.insert(item, count.min(wanted));
+        }
+    }
+
+    if let Other::Collection(theirs) = other {
+        for (item, count) in surplus(theirs, data, rules) {
+            let wanted = rules.wanted(inventory, &item, data);
+            if wanted > 0 {
+                matches.receive.insert(item, count.min(wanted));
+            }
+        }
+    }
+
+    matches
+}
+
+/// The surplus and any matches as records, with the count being the number
+/// to trade.
+#[derive(Default, Serialize)]
+pub struct TradeLists {
+    pub surplus: Records,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub give: Option<Records>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receive: Option<Records>,
+}
+
+impl TradeLists {
+    pub fn build(
+        inventory: &Inventory,
+        other: Option<&Other>,
+        data: &Data,
+        catalog: &Catalog,
+        rules: &KeepRules,
+    ) -> (TradeLists, Vec<Error>) {
+        let mut errors = vec![];
+        let mut records = |inventory: &Inventory| {
+            let (r, mut e) = Records::build(inventory, data, catalog);
+            errors.append(&mut e);
+            r
+        };
+
+        let mut lists = TradeLists {
+            surplus: records(&surplus(inventory, data, rules)),
+            ..Default::default()
+        };
+        if let Some(other) = other {
+            let m = matches(inventory, other, data, rules);
+            lists.give = Some(records(&m.give));
+            lists.receive = Some(records(&m.receive));
+        }
+        (lists, errors)
+    }
+
+    fn lists(&self) -> Vec<(&str, &Records)> {
+        let mut lists = vec![("surplus", &self.surplus)];
+        if let Some(r) = &self.give {
+            lists.push(("give", r));
+        }
+        if let Some(r) = &self.receive {
+            lists.push(("receive", r));
+        }
+        lists
+    }
+
+    /// The records of one type from all the lists, with a leading `list`
+    /// column. The other columns are the record fields, in order.
+    fn table(&self, r#type: ItemType) -> (Vec<String>, Vec<Vec<Value>>) {
+        let mut headers = vec!["list".to_owned()];
+        let mut objects: Vec<(&str, Map<String, Value>)> = vec![];
+
+        for (list, records) in self.lists() {
+            let values = match r#type {
+                ItemType::Ship => to_values(&records.ships),
+                ItemType::Pilot => to_values(&records.pilots),
+                _ => to_values(&records.upgrades),
+            };
+            for v in values {
+                if let Value::Object(map) = v {
+                    // sources are only serialized when known
+                    for k in map.keys() {
+                        if !headers.contains(k) {
+                            headers.push(k.to_owned());
+                        }
+                    }
+                    objects.push((list, map));
+                }
+            }
+        }
+
+        let rows = objects
+            .into_iter()
+            .map(|(list, map)| {
+                headers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, h)| match i {
+                        0 => Value::String(list.to_owned()),
+                        _ => map.get(h).cloned().unwrap_or(Value::Null),
+                    })
+                    .collect()
+            })
+            .collect();
+        (headers, rows)
+    }
+
+    /// Writes the ships, pilots or upgrades of all the lists as csv.
+    pub fn write_csv<W: Write>(&self, r#type: ItemType, writer: W) -> Result<(), Error> {
+        let (headers, rows) = self.table(r#type);
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(&headers)?;
+        for row in rows {
+            writer.write_record(row.iter().map(|v| match v {
+                Value::String(s) => s.to_owned(),
+                Value::Null => String::new(),
+                v => v.to_string(),
+            }))?;
+        }
+        writer.flush().map_err(csv::Error::from)?;
+        Ok(())
+    }
+
+    /// A workbook with a Ships, Pilots and Upgrades sheet, each with a table
+    /// of all the lists.
+    pub fn workbook(&self) -> Result<Workbook, Error> {
+        let mut workbook = Workbook::new();
+        for (name, table_name, r#type) in [
+            ("Ships", "tradeShipTable", ItemType::Ship),
+            ("Pilots", "tradePilotTable", ItemType::Pilot),
+            ("Upgrades", "tradeUpgradeTable", ItemType::Upgrade),
+        ] {
+            let (headers, rows) = self.table(r#type);
+            add_sheet(&mut workbook, name, table_name, &headers, &rows)?;
+        }
+        Ok(workbook)
+    }
+}
+
+fn to_values<T: Serialize>(records: &[T]) -> Vec<Value> {
+    records
+        .iter()
+        .filter_map(|r| serde_json::to_value(r).ok())
+        .collect()
+}
+
+fn add_sheet(
+    workbook: &mut Workbook,
+    name: &str,
+    table_name: &str,
+    headers: &[String],
+    rows: &[Vec<Value>],
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name(name)?;
+    for (col, h) in headers.iter().enumerate() {
+        sheet.write(0, col as u16, h)?;
+    }
+    for (i, row) in rows.iter().enumerate() {
+        for (col, v) in row.iter().enumerate() {
+            let (r, c) = (i as u32 + 1, col as u16);
+            match v {
+                Value::String(s) => sheet.write(r, c, s)?,
+                Value::Number(n) => sheet.write(r, c, n.as_f64().unwrap_or_default())?,
+                Value::Bool(b) => sheet.write(r, c, *b)?,
+                _ => continue,
+            };
+        }
+    }
+    let mut table = Table::new();
+    let table = table.set_name(table_name).set_style(TableStyle::Medium7);
+    sheet.add_table(
+        0,
+        0,
+        (rows.len() as u32).max(1),
+        headers.len() as u16 - 1,
+        table,
+    )?;
+    sheet.autofit();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xwingdata2::fixtures::{pilot, ship};
+    use crate::xwingdata2::Pilot;
+
+    fn data() -> Data {
+        Data {
+            ships: vec![ship(
+                "TIE/ln Fighter",
+                "tielnfighter",
+                "galacticempire",
+                vec![
+                    pilot("blacksquadronace"),
+                    Pilot {
+                        limited: 1,
+                        ..pilot("idenversio")
+                    },
+                ],
+            )],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_surplus() {
+        let tie = Item::new(ItemType::Ship, "tielnfighter");
+        let ace = Item::new(ItemType::Pilot, "blacksquadronace");
+        let iden = Item::new(ItemType::Pilot, "idenversio");
+        let inventory = Inventory::from([(tie.clone(), 3), (ace.clone(), 10), (iden.clone(), 3)]);
+
+        assert_eq!(
+            surplus(&inventory, &data(), &KeepRules::default()),
+            Inventory::from([(tie.clone(), 2), (ace.clone(), 9), (iden.clone(), 2)])
+        );
+
+        let rules = KeepRules {
+            ships: Keep::Count(3),
+            pilots: Keep::ListMax,
+            upgrades: Keep::Count(2),
+        };
+        assert_eq!(
+            surplus(&inventory, &data(), &rules),
+            Inventory::from([(ace, 10 - LIST_MAX), (iden, 2)])
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let tie = Item::new(ItemType::Ship, "tielnfighter");
+        let ace = Item::new(ItemType::Pilot, "blacksquadronace");
+        let iden = Item::new(ItemType::Pilot, "idenversio");
+        let mine = Inventory::from([(tie.clone(), 4), (iden.clone(), 0)]);
+        let theirs = Inventory::from([(iden.clone(), 2), (ace.clone(), 1)]);
+
+        let m = matches(
+            &mine,
+            &Other::Collection(theirs),
+            &data(),
+            &KeepRules::default(),
+        );
+        assert_eq!(m.give, Inventory::from([(tie.clone(), 1)]));
+        assert_eq!(m.receive, Inventory::from([(iden, 1)]));
+
+        let wants = Inventory::from([(tie.clone(), 5), (ace, 1)]);
+        let m = matches(&mine, &Other::Wants(wants), &data(), &KeepRules::default());
+        assert_eq!(m.give, Inventory::from([(tie, 3)]));
+        assert!(m.receive.is_empty());
+    }
+
+    #[test]
+    fn test_keep_from_str() {
+        assert_eq!("2".parse::<Keep>().unwrap(), Keep::Count(2));
+        assert_eq!("List".parse::<Keep>().unwrap(), Keep::ListMax);
+        assert!("lots".parse::<Keep>().is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! The upgrades a pilot can equip, going by the upgrade restrictions and the
+//! pilot's slots, and how many of them are in an inventory.
+//!
+//! An upgrade fits if the slots of its front side are all free on the pilot,
+//! e.g. a Crew and Gunner upgrade needs both. Restrictions on other upgrades
+//! being equipped are checked against the pilot's slots, so they are allowed
+//! as long as the pilot could equip one.
+use serde::Serialize;
+
+use crate::expansions::{Item, ItemType, XWS};
+use crate::xwingdata2::{Data, SlotKind, Upgrade};
+use crate::{Error, Inventory};
+
+/// An upgrade the pilot can equip.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct UsableUpgrade {
+    pub xws: XWS,
+    pub name: String,
+    pub owned: u32,
+}
+
+/// The upgrades for one of the pilot's slot types.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SlotUpgrades {
+    pub slot: SlotKind,
+    /// The number of these slots the pilot has.
+    pub slots: usize,
+    pub upgrades: Vec<UsableUpgrade>,
+}
+
+impl SlotUpgrades {
+    /// The number of different upgrades that are owned.
+    pub fn owned_unique(&self) -> usize {
+        self.upgrades.iter().filter(|u| u.owned > 0).count()
+    }
+
+    /// The number of upgrade cards that are owned.
+    pub fn owned(&self) -> u32 {
+        self.upgrades.iter().map(|u| u.owned).sum()
+    }
+}
+
+/// Whether every one of `slots` is free in `available`, counting duplicates.
+pub fn fits(slots: &[SlotKind], available: &[SlotKind]) -> bool {
+    let mut available = available.to_vec();
+    slots
+        .iter()
+        .all(|s| match available.iter().position(|a| a == s) {
+            Some(i) => {
+                available.swap_remove(i);
+                true
+            }
+            None => false,
+        })
+}
+
+/// Every upgrade the pilot can equip, grouped by the upgrade type in the
+/// order of the pilot's slots.
+pub fn for_pilot(
+    pilot: &str,
+    data: &Data,
+    inventory: &Inventory,
+) -> Result<Vec<SlotUpgrades>, Error> {
+    let (_, p) = data.get_pilot(pilot).ok_or(Error::UnknownXws(Item {
+        r#type: ItemType::Pilot,
+        xws: pilot.to_owned(),
+    }))?;
+
+    let mut groups: Vec<SlotUpgrades> = vec![];
+    for slot in &p.slots {
+        match groups.iter_mut().find(|g| g.slot == *slot) {
+            Some(g) => g.slots += 1,
+            None => groups.push(SlotUpgrades {
+                slot: *slot,
+                slots: 1,
+                upgrades: vec![],
+            }),
+        }
+    }
+
+    for u in data.allowed_upgrades(pilot, &p.slots) {
+        let Some(side) = u.sides.first() else {
+            continue;
+        };
+        if !fits(&side.slots, &p.slots) {
+            continue;
+        }
+        if let Some(g) = groups.iter_mut().find(|g| g.slot == side.r#type) {
+            g.upgrades.push(usable(u, inventory));
+        }
+    }
+    for g in groups.iter_mut() {
+        g.upgrades.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    Ok(groups)
+}
+
+fn usable(upgrade: &Upgrade, inventory: &Inventory) -> UsableUpgrade {
+    let owned = inventory
+        .get(&Item {
+            r#type: ItemType::Upgrade,
+            xws: upgrade.xws.to_owned(),
+        })
+        .copied()
+        .unwrap_or(0);
+    UsableUpgrade {
+        xws: upgrade.xws.to_owned(),
+        name: upgrade.name.to_owned(),
+        owned,
+    }
+}
+
+/// A slot type of an owned pilot and the owned upgrades that fit it.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SlotAvailability {
+    pub pilot: XWS,
+    pub name: String,
+    pub ship: String,
+    pub faction: String,
+    pub slot: SlotKind,
+    pub slots: usize,
+    /// The number of different owned upgrades.
+    pub upgrades: usize,
+    /// The number of owned upgrade cards.
+    pub cards: u32,
+}
+
+/// Every slot type of every owned pilot, sorted by pilot xws.
+pub fn availability(data: &Data, inventory: &Inventory) -> Vec<SlotAvailability> {
+    let mut rows = vec![];
+    for (item, count) in inventory {
+        if item.r#type != ItemType::Pilot || *count == 0 {
+            continue;
+        }
+        let (Some((s, p)), Ok(groups)) = (
+            data.get_pilot(&item.xws),
+            for_pilot(&item.xws, data, inventory),
+        ) else {
+            continue;
+        };
+        for g in groups {
+            rows.push(SlotAvailability {
+                pilot: p.xws.to_owned(),
+                name: p.name.to_owned(),
+                ship: s.name.to_owned(),
+                faction: data
+                    .get_faction(&s.faction)
+                    .map_or(s.faction.to_owned(), |f| f.name.to_owned()),
+                slot: g.slot,
+                slots: g.slots,
+                upgrades: g.owned_unique(),
+                cards: g.owned(),
+            });
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xwingdata2::fixtures::{pilot, ship};
+    use crate::xwingdata2::Pilot;
+
+    fn upgrade(json: &str) -> Upgrade {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_for_pilot() {
+        let data = Data {
+            ships: vec![ship(
+                "T-70 X-wing",
+                "t70xwing",
+                "resistance",
+                vec![Pilot {
+                    slots: vec![SlotKind::Astromech, SlotKind::Tech, SlotKind::Tech],
+                    ..pilot("poedameron")
+                }],
+            )],
+            upgrades: vec![
+                upgrade(
+                    r#"{"name": "R2-HA", "xws": "r2ha", "sides": [{"type": "Astromech", "slots": ["Astromech"]}],
+                        "restrictions": [{"factions": ["resistance"]}]}"#,
+                ),
+                upgrade(
+                    r#"{"name": "R2-D2", "xws": "r2d2", "sides": [{"type": "Astromech", "slots": ["Astromech"]}],
+                        "restrictions": [{"factions": ["rebelalliance"]}]}"#,
+                ),
+                upgrade(
+                    r#"{"name": "Targeting Synchronizer", "xws": "targetingsynchronizer",
+                        "sides": [{"type": "Tech", "slots": ["Tech"]}]}"#,
+                ),
+                upgrade(
+                    r#"{"name": "Big Tech", "xws": "bigtech",
+                        "sides": [{"type": "Tech", "slots": ["Tech", "Tech", "Tech"]}]}"#,
+                ),
+                upgrade(
+                    r#"{"name": "Fire-Control System", "xws": "firecontrolsystem",
+                        "sides": [{"type": "Sensor", "slots": ["Sensor"]}]}"#,
+                ),
+            ],
+            ..Default::default()
+        };
+        let inventory = Inventory::from([
+            (Item::new(ItemType::Pilot, "poedameron"), 1),
+            (Item::new(ItemType::Upgrade, "r2ha"), 2),
+        ]);
+
+        let groups = for_pilot("poedameron", &data, &inventory).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].slot, SlotKind::Astromech);
+        assert_eq!(
+            groups[0].upgrades,
+            vec![UsableUpgrade {
+                xws: "r2ha".to_owned(),
+                name: "R2-HA".to_owned(),
+                owned: 2,
+            }]
+        );
+        assert_eq!(groups[1].slots, 2);
+        assert_eq!(groups[1].upgrades.len(), 1);
+        assert!(for_pilot("nobody", &data, &inventory).is_err());
+
+        let rows = availability(&data, &inventory);
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].upgrades, rows[0].cards), (1, 2));
+        assert_eq!((rows[1].upgrades, rows[1].cards), (0, 0));
+    }
+}
@@ -0,0 +1,168 @@
+//! Answers "What am I actually missing out on if I don't pick up an
+//! expansion?" by splitting the contents of each expansion that isn't owned
+//! into items that would be new to the collection and duplicates.
+//!
+//! Expansions are ranked by their new ships, pilots and upgrades, components
+//! like dials are only counted separately. Placeholders, like `swzunreleased`,
+//! can't be bought so are left out.
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::expansions::{Catalog, Item, ItemType, ProductKind, SKU};
+use crate::xwingdata2::Data;
+use crate::{Collection, Inventory};
+
+/// What buying one copy of an expansion would add to a collection.
+#[derive(Debug, PartialEq)]
+pub struct ExpansionValue {
+    pub sku: SKU,
+    pub name: String,
+    pub wave: u32,
+    pub kind: ProductKind,
+    /// Items that aren't in the collection at all.
+    pub new: Vec<(Item, u32)>,
+    /// Items that are already in the collection.
+    pub duplicates: Vec<(Item, u32)>,
+    /// The number of unique new items of each type.
+    pub new_ships: u32,
+    pub new_pilots: u32,
+    pub new_upgrades: u32,
+    /// The number of unique new components, see [`ItemType::is_component`].
+    pub new_components: u32,
+    /// The number of unique new pilots per faction display name.
+    pub factions: BTreeMap<String, u32>,
+}
+
+impl ExpansionValue {
+    /// The unique new ships, pilots and upgrades.
+    pub fn new_unique(&self) -> u32 {
+        self.new_ships + self.new_pilots + self.new_upgrades
+    }
+}
+
+/// The value of every product in the catalog that isn't in the collection,
+/// sorted with the most new unique ships, pilots and upgrades first.
+pub fn build(
+    catalog: &Catalog,
+    data: &Data,
+    collection: &Collection,
+    inventory: &Inventory,
+) -> Vec<ExpansionValue> {
+    let mut values = vec![];
+
+    for (sku, expansion) in &catalog.expansions {
+        if *collection.skus.get(sku).unwrap_or(&0) > 0 || !expansion.kind.is_product() {
+            continue;
+        }
+
+        let mut value = ExpansionValue {
+            sku: sku.to_owned(),
+            name: expansion.name.to_owned(),
+            wave: expansion.wave,
+            kind: expansion.kind,
+            new: vec![],
+            duplicates: vec![],
+            new_ships: 0,
+            new_pilots: 0,
+            new_upgrades: 0,
+            new_components: 0,
+            factions: BTreeMap::new(),
+        };
+
+        for c in &expansion.contents {
+            if *inventory.get(&c.item).unwrap_or(&0) > 0 {
+                value.duplicates.push((c.item.clone(), c.count));
+                continue;
+            }
+
+            match c.item.r#type {
+                ItemType::Ship => value.new_ships += 1,
+                ItemType::Upgrade => value.new_upgrades += 1,
+                ItemType::Pilot => {
+                    value.new_pilots += 1;
+                    if let Some((ship, _)) = data.get_pilot(&c.item.xws) {
+                        let faction = data
+                            .get_faction(&ship.faction)
+                            .map_or(ship.faction.to_owned(), |f| f.name.to_owned());
+                        *value.factions.entry(faction).or_insert(0) += 1;
+                    }
+                }
+                t if t.is_component() => value.new_components += 1,
+                _ => (),
+            }
+            value.new.push((c.item.clone(), c.count));
+        }
+
+        values.push(value);
+    }
+
+    values.sort_by(|a, b| match b.new_unique().cmp(&a.new_unique()) {
+        Ordering::Equal => a.wave.cmp(&b.wave).then(a.sku.cmp(&b.sku)),
+        o => o,
+    });
+    values
+}
+
+/// `Faction:count` pairs, joined like the other list columns.
+pub fn format_factions(factions: &BTreeMap<String, u32>) -> String {
+    factions
+        .iter()
+        .map(|(f, c)| format!("{}:{}", f, c))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let catalog = Catalog::from_json(
+            Path::new("test.json"),
+            r#"[
+                {"sku": "core", "name": "Core", "wave": 0, "contents": [
+                    {"type": "ship", "xws": "t65xwing", "count": 1},
+                    {"type": "ship", "xws": "tielnfighter", "count": 2}
+                ]},
+                {"sku": "xwing", "name": "X-Wing", "wave": 1, "contents": [
+                    {"type": "ship", "xws": "t65xwing", "count": 1},
+                    {"type": "upgrade", "xws": "r2d2", "count": 1}
+                ]},
+                {"sku": "tie", "name": "TIE", "wave": 1, "contents": [
+                    {"type": "ship", "xws": "tielnfighter", "count": 1},
+                    {"type": "dial", "xws": "tielnfighter", "count": 1},
+                    {"type": "template", "xws": "maneuver", "count": 1}
+                ]},
+                {"sku": "unreleased", "name": "Unreleased", "wave": 99, "kind": "placeholder",
+                 "contents": [
+                    {"type": "ship", "xws": "ewing", "count": 1}
+                ]}
+            ]"#,
+        )
+        .unwrap();
+        let data = Data {
+            ships: vec![],
+            upgrades: vec![],
+            factions: vec![],
+            ..Default::default()
+        };
+
+        let mut collection = Collection::default();
+        collection.add_expansion("core", 1);
+        let (inventory, _) = collection.inventory(&catalog, &Default::default());
+
+        let values = build(&catalog, &data, &collection, &inventory);
+        assert_eq!(
+            values.iter().map(|v| v.sku.as_str()).collect::<Vec<_>>(),
+            vec!["xwing", "tie"]
+        );
+        assert_eq!(values[0].new_upgrades, 1);
+        assert_eq!(values[0].duplicates.len(), 1);
+        // the dial and templates don't make it worth more
+        assert_eq!(values[1].new_unique(), 0);
+        assert_eq!(values[1].new_components, 2);
+    }
+}
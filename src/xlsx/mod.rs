@@ -0,0 +1,251 @@
+//! Support for reading back a spreadsheet produced by `generate_xls` after it
+//! has been filled in.
+//!
+//! Only the columns meant to be edited are read: `Owned` in the
+//! `ExpansionLookup` table and `Singles` in the `ShipTable`, `pilotTable`,
+//! `upgradeTable`, `obstacleTable`, `damageDeckTable` and `componentTable`
+//! tables. The `Total` columns are just formulas over those, so they are
+//! ignored. The `componentTable` has every component type, so its `Type`
+//! column is read too. The `Singles` of the rows with the same xws, like the
+//! sides of an upgrade, are added up.
+//!
+//! Spreadsheets generated before obstacles, damage decks and components were
+//! included don't have those tables, so they are optional.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use xwingtmg2_inventory_rs::xlsx;
+//!
+//! let collection = xlsx::load(Path::new("XWingTMG2_Inventory.xlsx")).unwrap();
+//! println!("{} expansions", collection.skus.len());
+//! ```
+use calamine::{open_workbook, Data, Reader, Xlsx};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::expansions::{Item, ItemType};
+use crate::{Collection, Error};
+
+/// Loads a collection from a spreadsheet file.
+pub fn load(path: &Path) -> Result<Collection, Error> {
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    read_collection(&mut workbook)
+}
+
+/// Loads a collection from an in-memory or otherwise already open spreadsheet.
+pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Collection, Error> {
+    let mut workbook = Xlsx::new(reader)?;
+    read_collection(&mut workbook)
+}
+
+fn read_collection<R: Read + Seek>(workbook: &mut Xlsx<R>) -> Result<Collection, Error> {
+    workbook.load_tables()?;
+
+    let mut collection = Collection {
+        skus: read_counts(workbook, "ExpansionLookup", &["SKU"], "Owned")?
+            .into_iter()
+            .map(|(mut keys, count)| (keys.remove(0), count))
+            .collect(),
+        singles: BTreeMap::new(),
+    };
+
+    for (table, r#type, optional) in [
+        ("ShipTable", ItemType::Ship, false),
+        ("pilotTable", ItemType::Pilot, false),
+        ("upgradeTable", ItemType::Upgrade, false),
+        ("obstacleTable", ItemType::Obstacle, true),
+        ("damageDeckTable", ItemType::Damage, true),
+    ] {
+        let counts = match read_counts(workbook, table, &["XWS"], "Singles") {
+            Err(Error::XlsxRead(calamine::XlsxError::TableNotFound(_))) if optional => continue,
+            r => r?,
+        };
+        for (mut keys, count) in counts {
+            let xws = keys.remove(0);
+            // double-sided upgrades can have a row per side
+            *collection.singles.entry(Item { r#type, xws }).or_insert(0) += count;
+        }
+    }
+
+    let counts = match read_counts(workbook, "componentTable", &["Type", "XWS"], "Singles") {
+        Err(Error::XlsxRead(calamine::XlsxError::TableNotFound(_))) => vec![],
+        r => r?,
+    };
+    for (keys, count) in counts {
+        let r#type = match ItemType::from_str(&keys[0]) {
+            Ok(t) if t.is_component() => t,
+            _ => {
+                eprintln!("xlsx: unknown component type {}", keys[0]);
+                continue;
+            }
+        };
+        *collection
+            .singles
+            .entry(Item {
+                r#type,
+                xws: keys[1].to_owned(),
+            })
+            .or_insert(0) += count;
+    }
+
+    Ok(collection)
+}
+
+/// Returns the non-zero counts in `count_col`, keyed by the values in the
+/// `key_cols`, for every row of the table.
+///
+/// calamine panics on tables without any data rows (e.g. an empty
+/// `upgradeTable`), so this only uses the table metadata to find the sheet and
+/// then reads the sheet directly. The generated tables always start at `A1`
+/// and any totals row is skipped since it has no key.
+fn read_counts<R: Read + Seek>(
+    workbook: &mut Xlsx<R>,
+    table: &str,
+    key_cols: &[&str],
+    count_col: &str,
+) -> Result<Vec<(Vec<String>, u32)>, Error> {
+    let sheet = workbook
+        .sheet_names()
+        .into_iter()
+        .find(|s| workbook.table_names_in_sheet(s).iter().any(|t| *t == table))
+        .ok_or_else(|| calamine::XlsxError::TableNotFound(table.to_owned()))?;
+    let range = workbook.worksheet_range(&sheet)?;
+    let mut rows = range.rows();
+
+    let header = rows.next().unwrap_or_default();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|c| c == name)
+            .ok_or_else(|| Error::MissingColumn {
+                table: table.to_owned(),
+                column: name.to_owned(),
+            })
+    };
+    let key_idxs = key_cols
+        .iter()
+        .map(|c| column(c))
+        .collect::<Result<Vec<_>, _>>()?;
+    let count_idx = column(count_col)?;
+
+    let mut counts = vec![];
+    'rows: for row in rows {
+        let mut keys = vec![];
+        for i in &key_idxs {
+            match row.get(*i) {
+                Some(Data::String(s)) if !s.is_empty() => keys.push(s.to_owned()),
+                _ => continue 'rows,
+            }
+        }
+        let name = keys.join(" ");
+        let count = parse_count(&name, row.get(count_idx).unwrap_or(&Data::Empty))?;
+        if count > 0 {
+            counts.push((keys, count));
+        }
+    }
+    Ok(counts)
+}
+
+/// Blank cells are 0, anything else has to be a whole, non-negative number.
+fn parse_count(name: &str, cell: &Data) -> Result<u32, Error> {
+    let count = match cell {
+        Data::Empty => Some(0),
+        Data::Int(i) => u32::try_from(*i).ok(),
+        Data::Float(f) if *f >= 0.0 && f.fract() == 0.0 && *f <= u32::MAX as f64 => Some(*f as u32),
+        Data::String(s) if s.trim().is_empty() => Some(0),
+        Data::String(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+    count.ok_or_else(|| Error::BadCount {
+        name: name.to_owned(),
+        count: cell.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::expansions::Catalog;
+    use crate::xwingdata2::fixtures::{pilot, ship};
+    use crate::xwingdata2::{self, Obstacle};
+
+    #[test]
+    fn test_round_trip() {
+        let catalog = Catalog::from_json(
+            Path::new("test.json"),
+            r#"[{"sku": "swz01", "name": "Core Set", "wave": 0, "contents": [
+                {"type": "ship", "xws": "t65xwing", "count": 1},
+                {"type": "pilot", "xws": "lukeskywalker", "count": 1}
+            ]}]"#,
+        )
+        .unwrap();
+        let data = xwingdata2::Data {
+            ships: vec![ship(
+                "T-65 X-wing",
+                "t65xwing",
+                "rebelalliance",
+                vec![pilot("lukeskywalker")],
+            )],
+            obstacles: vec![Obstacle {
+                name: "Core Asteroid 0".to_owned(),
+                xws: "coreasteroid0".to_owned(),
+                r#type: None,
+            }],
+            ..Default::default()
+        };
+
+        let luke = Item::new(ItemType::Pilot, "lukeskywalker");
+        let mut collection = Collection::default();
+        collection.skus.insert("swz01".to_owned(), 2);
+        collection.singles.insert(luke.clone(), 3);
+        collection.add_single(
+            Item {
+                r#type: ItemType::Obstacle,
+                xws: "coreasteroid0".to_owned(),
+            },
+            1,
+        );
+        collection.add_single(
+            Item {
+                r#type: ItemType::Dial,
+                xws: "t65xwing".to_owned(),
+            },
+            1,
+        );
+        let (inventory, _) = collection.inventory(&catalog, &Default::default());
+
+        let buffer = crate::generate_xls_to_buffer(
+            &catalog,
+            &data,
+            &collection,
+            &inventory,
+            &crate::SheetOptions {
+                dials: true,
+                upgrade_sides: true,
+                availability: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let read = from_reader(Cursor::new(buffer)).unwrap();
+
+        assert_eq!(read.skus, collection.skus);
+        assert_eq!(read.singles, collection.singles);
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("x", &Data::Empty).unwrap(), 0);
+        assert_eq!(parse_count("x", &Data::Float(2.0)).unwrap(), 2);
+        assert_eq!(
+            parse_count("x", &Data::String(" 3 ".to_owned())).unwrap(),
+            3
+        );
+        assert!(parse_count("x", &Data::Float(1.5)).is_err());
+        assert!(parse_count("x", &Data::Int(-1)).is_err());
+    }
+}
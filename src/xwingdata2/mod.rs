@@ -6,18 +6,29 @@
 //! use xwingtmg2_inventory_rs::xwingdata2::Data;
 //!
 //! let data = Data::load_from_manifest(Path::new("./xwing-data2")).unwrap();
+//! // or, with the default `embedded` feature:
+//! // let data = Data::load_embedded().unwrap();
 //! match data.get_pilot("zeborrelios") {
 //!    Some((ship, pilot)) => println!("{}: {} - {}", ship.name, pilot.name, pilot.initiative),
 //!    None => println!("not found"),
 //! };
 //! ```
 
-use serde::{Deserialize, Serialize};
-use std::fs;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::io::Error;
 use std::path::Path;
 
+use crate::Error;
+
+/// The xwing-data2 json files included at build time, keyed by their path
+/// relative to the root of the xwing-data2 repo, as used in the manifest.
+#[cfg(feature = "embedded")]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/xwing_data2.rs"));
+}
+
 #[derive(Deserialize, Serialize, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Debug)]
 pub enum SlotKind {
     Astromech,
@@ -54,8 +65,7 @@ pub enum XwsKind {
     Obstacle,
     #[serde(alias = "pilot")]
     Pilot,
-    //TODO: Using the "type" of the main side for now, but should be expanded
-    // to account for the multiple slot cards
+    /// The type of the front side of the upgrade.
     #[serde(alias = "upgrade")]
     Upgrade(SlotKind),
     #[serde(alias = "damage")]
@@ -123,12 +133,76 @@ pub fn known_missing(xws: &str) -> bool {
     matches!(xws, "sabinewren-swz93") // this is the epic commmand/crew card
 }
 
-#[derive(Deserialize, Clone, Debug)]
+/// Charges and how many recover at the end of each round.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct Charges {
+    pub value: u32,
+    #[serde(default)]
+    pub recovers: i32,
+}
+
+/// Force, like [`Charges`], and the sides of the force it's on.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct Force {
+    pub value: u32,
+    #[serde(default)]
+    pub recovers: i32,
+    #[serde(default)]
+    pub side: Vec<String>,
+}
+
+/// A ship ability printed on the pilot card, like `Full Throttle`.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct ShipAbility {
+    pub name: String,
+    pub text: String,
+}
+
+/// Some numbers are strings for a few cards, like a `"?"` cost, so anything
+/// that isn't a number is treated as unknown instead of failing to load.
+fn number_or_string<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u32),
+        String(String),
+    }
+    Ok(match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::Number(n)) => Some(n),
+        Some(NumberOrString::String(s)) => s.trim().parse().ok(),
+        None => None,
+    })
+}
+
+#[derive(Deserialize, Clone, Default, Debug)]
 pub struct Pilot {
     pub name: String,
     pub caption: Option<String>,
     pub xws: String,
     pub initiative: u32,
+    /// The number of copies allowed in a list, 0 if there is no limit.
+    #[serde(default)]
+    pub limited: u32,
+    /// The points cost, if known.
+    #[serde(default, deserialize_with = "number_or_string")]
+    pub cost: Option<u32>,
+    /// The loadout value that can be spent on upgrades.
+    #[serde(default, deserialize_with = "number_or_string")]
+    pub loadout: Option<u32>,
+    #[serde(default)]
+    pub slots: Vec<SlotKind>,
+    pub charges: Option<Charges>,
+    pub force: Option<Force>,
+    pub ability: Option<String>,
+    /// The flavor text, for the pilots without an ability.
+    pub text: Option<String>,
+    #[serde(alias = "shipAbility")]
+    pub ship_ability: Option<ShipAbility>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
     #[serde(alias = "standardLoadout")]
     pub standard_loadout: Option<Vec<String>>,
 }
@@ -142,15 +216,80 @@ impl XwsId for Pilot {
     }
 }
 
-#[derive(Deserialize, Debug)]
+/// A chassis stat: an attack with its arc, agility, hull, shields or energy.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct Stat {
+    pub r#type: String,
+    #[serde(default)]
+    pub value: u32,
+    pub arc: Option<String>,
+    pub recovers: Option<u32>,
+}
+
+/// An action in the action bar, and the action linked to it, if any.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct Action {
+    pub r#type: String,
+    pub difficulty: Option<String>,
+    pub linked: Option<Box<Action>>,
+}
+
+impl fmt::Display for Action {
+    /// e.g. `Barrel Roll > Red Focus`, leaving out the usual White.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.difficulty.as_deref() {
+            None | Some("White") => write!(f, "{}", self.r#type)?,
+            Some(d) => write!(f, "{} {}", d, self.r#type)?,
+        }
+        if let Some(linked) = &self.linked {
+            write!(f, " > {}", linked)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Default, Debug)]
 pub struct Ship {
     pub name: String,
     pub xws: String,
     pub faction: String,
     pub size: String,
+    #[serde(default)]
+    pub stats: Vec<Stat>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    /// The maneuvers, as speed, bearing and difficulty codes, e.g. `1TW`.
+    #[serde(default)]
+    pub dial: Vec<String>,
+    /// The codes printed on the physical dial, to match it to the ship.
+    #[serde(default, alias = "dialCodes")]
+    pub dial_codes: Vec<String>,
     pub pilots: Vec<Pilot>,
 }
 
+impl Ship {
+    /// The value of a stat that isn't an attack, like `hull`.
+    pub fn stat(&self, r#type: &str) -> Option<u32> {
+        self.stats
+            .iter()
+            .find(|s| s.r#type == r#type)
+            .map(|s| s.value)
+    }
+
+    /// The attacks with their arcs, e.g. `Front Arc 3, Rear Arc 1`.
+    pub fn attacks(&self) -> String {
+        self.stats
+            .iter()
+            .filter(|s| s.r#type == "attack")
+            .map(|s| match &s.arc {
+                Some(arc) => format!("{} {}", arc, s.value),
+                None => s.value.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
 impl XwsId for Ship {
     fn xws(&self) -> &str {
         &self.xws
@@ -160,10 +299,22 @@ impl XwsId for Ship {
     }
 }
 
+/// One side of an upgrade card. Most cards only have one, but
+/// configurations and some titles, like Pivot Wing, have two.
 #[derive(Deserialize, Debug)]
 pub struct Side {
+    /// The name on this side, when it isn't just the card's name, e.g.
+    /// `Pivot Wing (Open)`.
+    pub title: Option<String>,
     pub r#type: SlotKind,
+    /// The slots the card takes while this side is up, e.g. both `Crew` and
+    /// `Gunner` for some cards.
     pub slots: Vec<SlotKind>,
+    pub ability: Option<String>,
+    /// The flavor text, for the sides without an ability.
+    pub text: Option<String>,
+    pub charges: Option<Charges>,
+    pub force: Option<Force>,
 }
 
 pub enum Restriction {
@@ -177,12 +328,18 @@ pub enum Restriction {
     //Action,
 }
 
-#[derive(Hash, Eq, PartialEq, Deserialize, Debug)]
+#[derive(Hash, Eq, PartialEq, Deserialize, Clone, Debug)]
 pub struct ActionDifficulty {
     pub r#type: String,
     pub difficulty: Option<String>,
 }
 
+/// One entry of an upgrade's `restrictions`, allowing the upgrade if any of
+/// its values match. Every entry of an upgrade has to allow it.
+///
+/// Only the keys below are modeled. Others, like `solitary` and
+/// `standardized`, and `equipped` values that aren't a [`SlotKind`] are
+/// ignored, so an entry with nothing else in it allows every pilot.
 #[derive(Hash, PartialEq, Eq, Deserialize, Default, Debug)]
 pub struct Restrictions {
     #[serde(default)]
@@ -203,48 +360,312 @@ pub struct Restrictions {
     pub action: Option<ActionDifficulty>,
 }
 
+impl Restrictions {
+    /// Every value as a [`Condition`], any of which allows the upgrade.
+    pub fn conditions(&self) -> Vec<Condition> {
+        let mut conditions = vec![];
+        for (values, condition) in [
+            (
+                &self.factions,
+                Condition::Faction as fn(String) -> Condition,
+            ),
+            (&self.sizes, Condition::Size),
+            (&self.ships, Condition::Ship),
+            (&self.arcs, Condition::Arc),
+            (&self.keywords, Condition::Keyword),
+            (&self.force_side, Condition::ForceSide),
+        ] {
+            conditions.extend(values.iter().cloned().map(condition));
+        }
+        // the upgrade types as in xwing-data2, e.g. `Force Power`
+        conditions.extend(
+            self.equipped
+                .iter()
+                .filter_map(|e| {
+                    let d: serde::de::value::StrDeserializer<serde::de::value::Error> =
+                        e.as_str().into_deserializer();
+                    SlotKind::deserialize(d).ok()
+                })
+                .map(Condition::Equipped),
+        );
+        conditions.extend(self.action.iter().cloned().map(Condition::Action));
+        conditions
+    }
+
+    /// Whether any of the conditions is met, or there aren't any that are
+    /// understood, see [`Restrictions`].
+    pub fn allows(&self, pilot: &Pilot, ship: &Ship, faction: &str, equipped: &[SlotKind]) -> bool {
+        let conditions = self.conditions();
+        conditions.is_empty()
+            || conditions
+                .iter()
+                .any(|c| c.allows(pilot, ship, faction, equipped))
+    }
+}
+
+/// A single requirement of an upgrade, for the pilot it is equipped to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// A faction xws.
+    Faction(String),
+    Size(String),
+    /// A ship xws.
+    Ship(String),
+    /// An arc the ship has an attack in, e.g. `Rear Arc`.
+    Arc(String),
+    /// A pilot keyword, e.g. `Jedi` or `TIE`.
+    Keyword(String),
+    /// `light` or `dark`.
+    ForceSide(String),
+    /// An upgrade type that has to be equipped too, e.g. `Crew`.
+    Equipped(SlotKind),
+    /// An action the ship has, in a difficulty, if any.
+    Action(ActionDifficulty),
+}
+
+impl Condition {
+    pub fn allows(&self, pilot: &Pilot, ship: &Ship, faction: &str, equipped: &[SlotKind]) -> bool {
+        match self {
+            Condition::Faction(f) => f == faction,
+            Condition::Size(s) => s.eq_ignore_ascii_case(&ship.size),
+            Condition::Ship(s) => *s == ship.xws,
+            Condition::Arc(a) => ship.stats.iter().any(|s| s.arc.as_ref() == Some(a)),
+            Condition::Keyword(k) => pilot.keywords.contains(k),
+            Condition::ForceSide(side) => pilot
+                .force
+                .as_ref()
+                .is_some_and(|f| f.side.iter().any(|s| s.eq_ignore_ascii_case(side))),
+            Condition::Equipped(slot) => equipped.contains(slot),
+            Condition::Action(a) => ship.actions.iter().any(|s| {
+                s.r#type == a.r#type
+                    && a.difficulty
+                        .as_ref()
+                        .is_none_or(|d| s.difficulty.as_ref() == Some(d))
+            }),
+        }
+    }
+
+    /// A description using the display names of factions and ships.
+    pub fn render(&self, data: &Data) -> String {
+        match self {
+            Condition::Faction(f) => data
+                .get_faction(f)
+                .map_or(f.to_owned(), |f| f.name.to_owned()),
+            Condition::Size(s) => format!("{} ship", s),
+            Condition::Ship(s) => data.get_ship_name(s).unwrap_or(s).to_owned(),
+            Condition::Arc(a) => a.to_owned(),
+            Condition::Keyword(k) => k.to_owned(),
+            Condition::ForceSide(s) => format!("{} side", s),
+            Condition::Equipped(s) => format!("{:?} equipped", s),
+            Condition::Action(a) => match a.difficulty.as_deref() {
+                None | Some("White") => format!("{} action", a.r#type),
+                Some(d) => format!("{} {} action", d, a.r#type),
+            },
+        }
+    }
+}
+
+/// The restrictions of an upgrade: every branch of an `All` has to allow it
+/// and at least one of an `Any`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RestrictionTree {
+    All(Vec<RestrictionTree>),
+    Any(Vec<RestrictionTree>),
+    Condition(Condition),
+}
+
+impl RestrictionTree {
+    pub fn allows(&self, pilot: &Pilot, ship: &Ship, faction: &str, equipped: &[SlotKind]) -> bool {
+        match self {
+            RestrictionTree::All(t) => t.iter().all(|t| t.allows(pilot, ship, faction, equipped)),
+            RestrictionTree::Any(t) => t.iter().any(|t| t.allows(pilot, ship, faction, equipped)),
+            RestrictionTree::Condition(c) => c.allows(pilot, ship, faction, equipped),
+        }
+    }
+
+    /// A human readable version, e.g. `(Rebel Alliance or Resistance) and
+    /// Small ship`, or an empty string for no restrictions.
+    pub fn render(&self, data: &Data) -> String {
+        let join = |trees: &[RestrictionTree], sep| {
+            trees
+                .iter()
+                .map(|t| match t {
+                    RestrictionTree::Condition(_) => t.render(data),
+                    _ if trees.len() == 1 => t.render(data),
+                    _ => format!("({})", t.render(data)),
+                })
+                .collect::<Vec<String>>()
+                .join(sep)
+        };
+        match self {
+            RestrictionTree::All(t) => join(t, " and "),
+            RestrictionTree::Any(t) => join(t, " or "),
+            RestrictionTree::Condition(c) => c.render(data),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Upgrade {
     pub name: String,
     pub xws: String,
+    /// The number of copies allowed in a list, 0 if there is no limit.
+    #[serde(default)]
+    pub limited: u32,
     pub sides: Vec<Side>,
 
     #[serde(default)]
     pub restrictions: Vec<Restrictions>,
 }
 
+impl Upgrade {
+    /// The restrictions as a tree, leaving out any without conditions that
+    /// are understood.
+    pub fn restriction_tree(&self) -> RestrictionTree {
+        RestrictionTree::All(
+            self.restrictions
+                .iter()
+                .map(|r| r.conditions())
+                .filter(|c| !c.is_empty())
+                .map(|c| match c.len() {
+                    1 => RestrictionTree::Condition(c.into_iter().next().unwrap()),
+                    _ => RestrictionTree::Any(
+                        c.into_iter().map(RestrictionTree::Condition).collect(),
+                    ),
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether the pilot can equip the upgrade, flying `ship` in `faction`
+    /// with the `equipped` upgrade types, ignoring the slots.
+    pub fn allows(&self, pilot: &Pilot, ship: &Ship, faction: &str, equipped: &[SlotKind]) -> bool {
+        self.restrictions
+            .iter()
+            .all(|r| r.allows(pilot, ship, faction, equipped))
+    }
+
+    /// Every type and slot on any side, without duplicates, e.g. `Crew` and
+    /// `Gunner` for a card that takes both slots.
+    pub fn slot_types(&self) -> Vec<SlotKind> {
+        let mut types = vec![];
+        for s in &self.sides {
+            for t in std::iter::once(&s.r#type).chain(&s.slots) {
+                if !types.contains(t) {
+                    types.push(*t);
+                }
+            }
+        }
+        types
+    }
+}
+
 impl XwsId for Upgrade {
     fn xws(&self) -> &str {
         &self.xws
     }
+    /// The type of the front side, see [`Upgrade::slot_types`] for the rest.
     fn kind(&self) -> XwsKind {
         XwsKind::Upgrade(self.sides[0].r#type)
     }
 }
 
-/// Top-level model of loaded xwing-data2 data.
 #[derive(Deserialize, Debug)]
+pub struct Obstacle {
+    #[serde(default)]
+    pub name: String,
+    pub xws: String,
+    /// e.g. asteroid, debris or gas cloud.
+    #[serde(default)]
+    pub r#type: Option<String>,
+}
+
+impl XwsId for Obstacle {
+    fn xws(&self) -> &str {
+        &self.xws
+    }
+    fn kind(&self) -> XwsKind {
+        XwsKind::Obstacle
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DamageCard {
+    #[serde(alias = "name")]
+    pub title: String,
+    /// The number of copies in a deck.
+    #[serde(default = "default_amount")]
+    pub amount: u32,
+    /// Pilot or Ship.
+    #[serde(default)]
+    pub r#type: String,
+    /// Not every damage deck has xws ids for the cards, so those are set to
+    /// the usual lowercase, alphanumeric only version of the title on load.
+    #[serde(default)]
+    pub xws: String,
+}
+
+fn default_amount() -> u32 {
+    1
+}
+
+impl XwsId for DamageCard {
+    fn xws(&self) -> &str {
+        &self.xws
+    }
+    fn kind(&self) -> XwsKind {
+        XwsKind::Damage
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DamageDeck {
+    #[serde(default)]
+    pub name: String,
+    pub cards: Vec<DamageCard>,
+}
+
+/// Top-level model of loaded xwing-data2 data.
+#[derive(Deserialize, Default, Debug)]
 pub struct Data {
     pub ships: Vec<Ship>,
     pub upgrades: Vec<Upgrade>,
     // List of factions loaded from the manifest for looking up a display name
     // from the xws id used to reference them.
     pub factions: Vec<Faction>,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub damage_decks: Vec<DamageDeck>,
 }
 
-fn load_type<T: for<'a> Deserialize<'a>>(root: &Path, paths: &[String]) -> Result<Vec<T>, Error> {
+fn load_type<T: for<'a> Deserialize<'a>>(
+    root: &Path,
+    read: &impl Fn(&str) -> Result<String, Error>,
+    paths: &[String],
+) -> Result<Vec<T>, Error> {
     let mut result = Vec::new();
 
     for path in paths {
-        let path = root.join(path);
-        let buffer = fs::read_to_string(path)?;
-        let mut factions: Vec<T> = serde_json::from_str(&buffer)?;
+        let buffer = read(path)?;
+        let mut factions: Vec<T> = crate::from_json(&root.join(path), &buffer)?;
         result.append(&mut factions);
     }
 
     Ok(result)
 }
 
+/// A copy of the field if it is the same in every faction of a ship,
+/// otherwise empty.
+fn same<T: Clone + PartialEq>(ships: &[&Ship], field: impl Fn(&Ship) -> &Vec<T>) -> Vec<T> {
+    let first = field(ships[0]);
+    if ships.iter().all(|s| field(s) == first) {
+        first.clone()
+    } else {
+        vec![]
+    }
+}
+
 impl Data {
     /// Loads from a xwing-data2/ data source.
     ///
@@ -253,23 +674,64 @@ impl Data {
     /// This function will return an error if any of the paths are invalid
     /// or can't be parsed.
     pub fn load_from_manifest(path: &Path) -> Result<Self, Error> {
+        Self::load_with(path, |p| crate::read_to_string(&path.join(p)))
+    }
+
+    /// Loads from the copy of xwing-data2 that was included at build time.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the submodule was not checked
+    /// out when building, or the included files can't be parsed.
+    #[cfg(feature = "embedded")]
+    pub fn load_embedded() -> Result<Self, Error> {
+        let root = Path::new("xwing-data2");
+        Self::load_with(root, |p| {
+            embedded::FILES
+                .iter()
+                .find(|(path, _)| *path == p)
+                .map(|(_, contents)| contents.to_string())
+                .ok_or_else(|| Error::Io {
+                    path: root.join(p),
+                    source: std::io::Error::new(std::io::ErrorKind::NotFound, "not embedded"),
+                })
+        })
+    }
+
+    /// Loads the manifest and everything it references using `read`, which
+    /// takes a path relative to the root of the xwing-data2 repo. `root` is
+    /// only used for error messages.
+    fn load_with(root: &Path, read: impl Fn(&str) -> Result<String, Error>) -> Result<Self, Error> {
         // read the whole manifest
-        let manifest_path = path.join("data/manifest.json");
-        let buffer = fs::read_to_string(manifest_path)?;
+        let manifest_path = "data/manifest.json";
+        let buffer = read(manifest_path)?;
 
-        let manifest: Manifest = serde_json::from_str(&buffer)?;
+        let manifest: Manifest = crate::from_json(&root.join(manifest_path), &buffer)?;
 
         let mut data = Data {
             ships: vec![],
-            upgrades: load_type(path, &manifest.upgrades)?,
-            factions: load_type(path, &manifest.factions)?,
+            upgrades: load_type(root, &read, &manifest.upgrades)?,
+            factions: load_type(root, &read, &manifest.factions)?,
+            obstacles: load_type(root, &read, &manifest.obstacles)?,
+            damage_decks: vec![],
         };
 
+        // each damage deck is a single object rather than a list
+        for path in &manifest.damagedecks {
+            let buffer = read(path)?;
+            let mut deck: DamageDeck = crate::from_json(&root.join(path), &buffer)?;
+            for card in &mut deck.cards {
+                if card.xws.is_empty() {
+                    card.xws = title_to_xws(&card.title);
+                }
+            }
+            data.damage_decks.push(deck);
+        }
+
         for faction in &manifest.pilots {
             for pilot_path in &faction.ships {
-                let path = path.join(pilot_path);
-                let buffer = fs::read_to_string(path)?;
-                let ship: Ship = serde_json::from_str(&buffer)?;
+                let buffer = read(pilot_path)?;
+                let ship: Ship = crate::from_json(&root.join(pilot_path), &buffer)?;
                 data.ships.push(ship);
             }
         }
@@ -302,7 +764,8 @@ impl Data {
 
     /// Returns a combined copy of just the model info, with factions joined
     /// into a single string. The models share the same xws ID, but are
-    /// listed multiple times across factions for some ships
+    /// listed multiple times across factions for some ships. The stats,
+    /// actions and dial are only kept when every faction has the same ones.
     pub fn get_ship_model(&self, xws: &str) -> Option<Ship> {
         match self
             .ships
@@ -314,13 +777,17 @@ impl Data {
             [] => None,
             [s] => Some(Ship {
                 name: s.name.clone(),
-                xws: s.name.clone(),
+                xws: s.xws.clone(),
                 size: s.size.clone(),
                 faction: self
                     .factions
                     .iter()
                     .find(|f| f.xws == s.faction)
                     .map_or(s.faction.clone(), |f| f.name.clone()),
+                stats: s.stats.clone(),
+                actions: s.actions.clone(),
+                dial: s.dial.clone(),
+                dial_codes: s.dial_codes.clone(),
                 pilots: vec![],
             }),
             s => Some(Ship {
@@ -337,14 +804,65 @@ impl Data {
                     })
                     .collect::<Vec<&str>>()
                     .join(","),
+                stats: same(s, |s| &s.stats),
+                actions: same(s, |s| &s.actions),
+                dial: same(s, |s| &s.dial),
+                dial_codes: same(s, |s| &s.dial_codes),
                 pilots: vec![],
             }),
         }
     }
 
+    /// The ship ability of a ship, which xwing-data2 only has on its pilots.
+    pub fn get_ship_ability(&self, xws: &str) -> Option<&ShipAbility> {
+        self.ships
+            .iter()
+            .filter(|s| s.xws == xws)
+            .flat_map(|s| &s.pilots)
+            .find_map(|p| p.ship_ability.as_ref())
+    }
+
+    /// The upgrades whose restrictions allow them on the pilot, ignoring the
+    /// slots, or nothing if the pilot isn't found.
+    pub fn allowed_upgrades(&self, pilot: &str, equipped: &[SlotKind]) -> Vec<&Upgrade> {
+        match self.get_pilot(pilot) {
+            Some((ship, pilot)) => self
+                .upgrades
+                .iter()
+                .filter(|u| u.allows(pilot, ship, &ship.faction, equipped))
+                .collect(),
+            None => vec![],
+        }
+    }
+
     pub fn get_faction(&self, xws: &str) -> Option<&Faction> {
         self.factions.iter().find(|&f| f.xws == xws)
     }
+
+    pub fn get_obstacle(&self, xws: &str) -> Option<&Obstacle> {
+        self.obstacles.iter().find(|&o| o.xws == xws)
+    }
+
+    /// Returns the first deck with the card and the card.
+    pub fn get_damage_card(&self, xws: &str) -> Option<(&DamageDeck, &DamageCard)> {
+        for d in &self.damage_decks {
+            for c in &d.cards {
+                if c.xws == xws {
+                    return Some((d, c));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// e.g. `Panicked Pilot` to `panickedpilot`.
+fn title_to_xws(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
 }
 
 #[derive(Deserialize, Debug)]
@@ -357,4 +875,264 @@ struct Manifest {
     pilots: Vec<ShipFaction>,
     upgrades: Vec<String>,
     factions: Vec<String>,
+    #[serde(default)]
+    obstacles: Vec<String>,
+    #[serde(default)]
+    damagedecks: Vec<String>,
+}
+
+/// Hand built data for the tests of the other modules.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// A small ship in one faction.
+    pub fn ship(name: &str, xws: &str, faction: &str, pilots: Vec<Pilot>) -> Ship {
+        Ship {
+            name: name.to_owned(),
+            xws: xws.to_owned(),
+            faction: faction.to_owned(),
+            size: "Small".to_owned(),
+            pilots,
+            ..Default::default()
+        }
+    }
+
+    /// An initiative 1 pilot named after its xws.
+    pub fn pilot(xws: &str) -> Pilot {
+        Pilot {
+            name: xws.to_owned(),
+            xws: xws.to_owned(),
+            initiative: 1,
+            ..Default::default()
+        }
+    }
+
+    /// A single sided upgrade taking one slot of its type.
+    pub fn upgrade(xws: &str, r#type: SlotKind) -> Upgrade {
+        Upgrade {
+            name: xws.to_owned(),
+            xws: xws.to_owned(),
+            limited: 0,
+            sides: vec![Side {
+                title: None,
+                r#type,
+                slots: vec![r#type],
+                ability: None,
+                text: None,
+                charges: None,
+                force: None,
+            }],
+            restrictions: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load_obstacles_and_damage() {
+        let files = [
+            (
+                "data/manifest.json",
+                r#"{"pilots": [], "upgrades": [], "factions": [],
+                "obstacles": ["data/obstacles.json"],
+                "damagedecks": ["data/damage-decks/core.json"]}"#,
+            ),
+            (
+                "data/obstacles.json",
+                r#"[{"name": "Core Asteroid 0", "xws": "coreasteroid0", "type": "asteroid"}]"#,
+            ),
+            (
+                "data/damage-decks/core.json",
+                r#"{"name": "Core", "cards": [
+                    {"title": "Panicked Pilot", "amount": 2, "type": "Pilot"},
+                    {"title": "Direct Hit!", "amount": 5, "type": "Ship", "xws": "directhit"}
+                ]}"#,
+            ),
+        ];
+        let data = Data::load_with(Path::new("test"), |p| {
+            Ok(files.iter().find(|(f, _)| *f == p).unwrap().1.to_owned())
+        })
+        .unwrap();
+
+        assert!(data.get_obstacle("coreasteroid0").is_some());
+        let (deck, card) = data.get_damage_card("panickedpilot").unwrap();
+        assert_eq!(deck.name, "Core");
+        assert_eq!(card.amount, 2);
+        assert!(data.get_damage_card("directhit").is_some());
+    }
+
+    #[test]
+    fn test_pilot() {
+        let pilot: Pilot = serde_json::from_str(
+            r#"{"name": "Luke Skywalker", "caption": "Red Five", "initiative": 5,
+                "limited": 1, "cost": 62, "loadout": "?", "xws": "lukeskywalker",
+                "ability": "After you become the defender, you may recover 1 Force.",
+                "shipAbility": {"name": "Full Throttle", "text": "..."},
+                "slots": ["Talent", "Force Power", "Astromech"],
+                "force": {"value": 2, "recovers": 1, "side": ["light"]},
+                "keywords": ["Jedi", "Light Side"]}"#,
+        )
+        .unwrap();
+        assert_eq!(pilot.cost, Some(62));
+        assert_eq!(pilot.loadout, None);
+        assert_eq!(pilot.slots[1], SlotKind::ForcePower);
+        assert_eq!(pilot.force.unwrap().side, vec!["light"]);
+        assert!(pilot.charges.is_none());
+        assert_eq!(pilot.ship_ability.unwrap().name, "Full Throttle");
+    }
+
+    #[test]
+    fn test_ship() {
+        let ship: Ship = serde_json::from_str(
+            r#"{"name": "T-65 X-wing", "xws": "t65xwing", "faction": "rebelalliance",
+                "size": "Small", "dial": ["1TW", "2FB"], "dialCodes": ["Xw"],
+                "stats": [
+                    {"arc": "Front Arc", "type": "attack", "value": 3},
+                    {"type": "agility", "value": 2},
+                    {"type": "hull", "value": 4},
+                    {"type": "shields", "value": 2}
+                ],
+                "actions": [
+                    {"difficulty": "White", "type": "Focus"},
+                    {"difficulty": "White", "type": "Barrel Roll",
+                     "linked": {"difficulty": "Red", "type": "Focus"}}
+                ],
+                "pilots": []}"#,
+        )
+        .unwrap();
+        assert_eq!(ship.attacks(), "Front Arc 3");
+        assert_eq!(ship.stat("hull"), Some(4));
+        assert_eq!(ship.stat("energy"), None);
+        assert_eq!(ship.actions[1].to_string(), "Barrel Roll > Red Focus");
+        assert_eq!(ship.dial_codes, vec!["Xw"]);
+    }
+
+    #[test]
+    fn test_ignored_restrictions() {
+        let upgrade: Upgrade = serde_json::from_str(
+            r#"{"name": "Test", "xws": "test", "sides": [],
+                "restrictions": [{"solitary": true}, {"equipped": ["Cloaking Device"]}]}"#,
+        )
+        .unwrap();
+        let ship = fixtures::ship("T-65 X-wing", "t65xwing", "rebelalliance", vec![]);
+
+        assert!(upgrade.restrictions[0].conditions().is_empty());
+        assert!(upgrade.restrictions[1].conditions().is_empty());
+        assert!(upgrade.allows(&Pilot::default(), &ship, "rebelalliance", &[]));
+        assert_eq!(upgrade.restriction_tree(), RestrictionTree::All(vec![]));
+
+        let r: Restrictions = serde_json::from_str(r#"{"equipped": ["Force Power"]}"#).unwrap();
+        assert_eq!(
+            r.conditions(),
+            vec![Condition::Equipped(SlotKind::ForcePower)]
+        );
+    }
+
+    #[test]
+    fn test_get_ship_model() {
+        let data = Data {
+            ships: vec![
+                Ship {
+                    dial: vec!["1TW".to_owned()],
+                    dial_codes: vec!["Z9".to_owned()],
+                    ..fixtures::ship("Z-95", "z95af4headhunter", "rebelalliance", vec![])
+                },
+                Ship {
+                    dial: vec!["1TW".to_owned()],
+                    dial_codes: vec!["Z9s".to_owned()],
+                    ..fixtures::ship("Z-95", "z95af4headhunter", "scumandvillainy", vec![])
+                },
+                fixtures::ship("T-65 X-wing", "t65xwing", "rebelalliance", vec![]),
+            ],
+            factions: vec![Faction {
+                xws: "rebelalliance".to_owned(),
+                name: "Rebel Alliance".to_owned(),
+            }],
+            ..Default::default()
+        };
+
+        // the xws, not the name, for both the single and multi-faction ships
+        let ship = data.get_ship_model("t65xwing").unwrap();
+        assert_eq!(ship.xws, "t65xwing");
+        assert_eq!(ship.faction, "Rebel Alliance");
+        let ship = data.get_ship_model("z95af4headhunter").unwrap();
+        assert_eq!(ship.xws, "z95af4headhunter");
+        assert_eq!(ship.faction, "Rebel Alliance,scumandvillainy");
+        // only what every faction agrees on
+        assert_eq!(ship.dial, vec!["1TW"]);
+        assert!(ship.dial_codes.is_empty());
+        assert!(data.get_ship_model("Z-95").is_none());
+    }
+
+    #[test]
+    fn test_upgrade_sides() {
+        let upgrade: Upgrade = serde_json::from_str(
+            r#"{"name": "Pivot Wing", "xws": "pivotwing", "limited": 1, "sides": [
+                {"title": "Pivot Wing (Closed)", "type": "Configuration",
+                 "slots": ["Configuration"], "ability": "While you defend, roll 1 fewer."},
+                {"title": "Pivot Wing (Open)", "type": "Configuration",
+                 "slots": ["Configuration"], "ability": "Before you activate, you may flip."}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(upgrade.sides.len(), 2);
+        assert_eq!(upgrade.sides[1].title.as_deref(), Some("Pivot Wing (Open)"));
+        assert_eq!(upgrade.slot_types(), vec![SlotKind::Configuration]);
+
+        let upgrade: Upgrade = serde_json::from_str(
+            r#"{"name": "Bossk", "xws": "bossk", "sides": [
+                {"type": "Crew", "slots": ["Crew", "Gunner"],
+                 "charges": {"value": 1, "recovers": 0}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(upgrade.slot_types(), vec![SlotKind::Crew, SlotKind::Gunner]);
+    }
+
+    #[test]
+    fn test_restrictions() {
+        let upgrade: Upgrade = serde_json::from_str(
+            r#"{"name": "Test", "xws": "test", "sides": [{"type": "Crew", "slots": ["Crew"]}],
+                "restrictions": [
+                    {"factions": ["rebelalliance", "resistance"]},
+                    {"sizes": ["Small"], "ships": ["yt1300"]},
+                    {"action": {"type": "Boost", "difficulty": "Red"}},
+                    {"equipped": ["Force Power"]}
+                ]}"#,
+        )
+        .unwrap();
+        let ship: Ship = serde_json::from_str(
+            r#"{"name": "T-65 X-wing", "xws": "t65xwing", "faction": "rebelalliance",
+                "size": "Small", "pilots": [],
+                "actions": [{"type": "Boost", "difficulty": "Red"}]}"#,
+        )
+        .unwrap();
+        let pilot = Pilot::default();
+        let data = Data {
+            factions: vec![Faction {
+                xws: "rebelalliance".to_owned(),
+                name: "Rebel Alliance".to_owned(),
+            }],
+            ..Default::default()
+        };
+
+        assert!(upgrade.allows(&pilot, &ship, "rebelalliance", &[SlotKind::ForcePower]));
+        assert!(!upgrade.allows(&pilot, &ship, "rebelalliance", &[]));
+        assert!(!upgrade.allows(&pilot, &ship, "galacticempire", &[SlotKind::ForcePower]));
+        assert!(upgrade.restriction_tree().allows(
+            &pilot,
+            &ship,
+            "resistance",
+            &[SlotKind::ForcePower]
+        ));
+        assert_eq!(
+            upgrade.restriction_tree().render(&data),
+            "(Rebel Alliance or resistance) and (Small ship or yt1300) \
+             and Red Boost action and ForcePower equipped"
+        );
+    }
 }
@@ -13,12 +13,13 @@ use serde::Deserialize;
 use serde::Serialize;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::fs;
-use std::io;
 use std::path::Path;
 
+use crate::compatibility::Compatibility;
 use crate::expansions;
-use crate::expansions::{Item, ItemType};
+use crate::expansions::{Item, ItemType, XWS};
+use crate::xwingdata2::Data;
+use crate::Error;
 
 /// Additional single items in a YASB colletion.
 #[derive(Deserialize, Serialize, Debug)]
@@ -46,14 +47,22 @@ impl Collection {
     /// Load a raw YASB collection obtained from <https://login.yash.app/collection>.
     /// Intermediate step that does not turn the strings for the counts back
     /// into numbers.
-    pub fn load(path: &Path) -> Result<Self, io::Error> {
-        let buffer = fs::read_to_string(path)?;
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let buffer = crate::read_to_string(path)?;
 
-        let f: CollectionFile = serde_json::from_str(&buffer)?;
+        let f: CollectionFile = crate::from_json(path, &buffer)?;
         Ok(f.collection)
     }
 }
 
+/// YASB stores counts as strings.
+fn parse_count(name: &str, count: &str) -> Result<u32, Error> {
+    count.trim().parse().map_err(|_| Error::BadCount {
+        name: name.to_owned(),
+        count: count.to_owned(),
+    })
+}
+
 /// A real basic function that can turn some YASB names into xws ids, or at
 /// least enough to disambiguate.
 pub fn to_canonical(name: &str) -> String {
@@ -66,120 +75,144 @@ pub fn to_canonical(name: &str) -> String {
         .collect::<String>()
 }
 
-/// This function implements special cases where the yasb name does not match
-/// the generated xws name or performs standard xws mapping for everything else.
+/// The `names.json` maintained in this repo.
+#[cfg(feature = "embedded")]
+const EMBEDDED_NAMES: &str = include_str!("names.json");
+
+/// The YASB names that don't turn into the right xws id with [`to_canonical`],
+/// per item type.
+///
+/// The keys are either the YASB name as is, or its canonical form, with the
+/// exact name checked first. That is needed for the legacy names with a bad
+/// capitalization, e.g. `E-Wing`, that canonicalize to the same id as the
+/// correct one.
+///
+/// An empty xws id marks a name that is skipped on import. YASB keeps stale
+/// records like `E-Wing` next to `E-wing` for a few current ships, and those
+/// would count the ship twice.
 ///
 /// It would be necessary to either import the full card list from YASB's source,
 /// which includes the xws id, but this seems like the least bad way to do this
 /// with the idea of supporting more collection sources.
-fn to_xws(name: &str, typ: expansions::ItemType) -> String {
-    let mut canonical = to_canonical(name);
-    // these particular bad capitilazation is problematic because it conflicts
-    // the correct one, which does correctly cannonicalize to xws.
-    match name {
-        "TIE/FO Fighter" | "E-Wing" | "T-70 X-Wing" | "TIE/SF Fighter" => {
-            canonical.push_str("-legacyyasb");
-            return canonical;
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct NameMap {
+    #[serde(default)]
+    pub ship: BTreeMap<String, XWS>,
+    #[serde(default)]
+    pub pilot: BTreeMap<String, XWS>,
+    #[serde(default)]
+    pub upgrade: BTreeMap<String, XWS>,
+}
+
+impl NameMap {
+    /// Loads the `names.json` that was included at build time.
+    #[cfg(feature = "embedded")]
+    pub fn load_embedded() -> Result<Self, Error> {
+        crate::from_json(Path::new("src/yasb2/names.json"), EMBEDDED_NAMES)
+    }
+
+    /// Loads a map from a `.json` or `.toml` file with the same sections.
+    pub fn load_from_path(path: &Path) -> Result<Self, Error> {
+        crate::from_json_or_toml(path)
+    }
+
+    /// Adds the names from `other`, replacing the ones already in the map,
+    /// so a user's own file only needs the names YASB changed since.
+    pub fn extend(&mut self, other: NameMap) {
+        self.ship.extend(other.ship);
+        self.pilot.extend(other.pilot);
+        self.upgrade.extend(other.upgrade);
+    }
+
+    /// The xws id for a YASB name, either from the map or the canonical form.
+    /// It's empty for the skipped names, see [`NameMap`].
+    pub fn to_xws(&self, name: &str, typ: ItemType) -> XWS {
+        let canonical = to_canonical(name);
+        let section = match typ {
+            ItemType::Ship => &self.ship,
+            ItemType::Pilot => &self.pilot,
+            ItemType::Upgrade => &self.upgrade,
+            _ => return canonical,
+        };
+        section
+            .get(name)
+            .or_else(|| section.get(&canonical))
+            .cloned()
+            .unwrap_or(canonical)
+    }
+}
+
+/// A YASB single that isn't a ship, pilot or upgrade in xwing-data2.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Unmapped {
+    pub r#type: ItemType,
+    pub name: String,
+    /// The xws id the name was turned into.
+    pub xws: XWS,
+    /// The closest xws ids of the same type, best first.
+    pub suggestions: Vec<XWS>,
+}
+
+/// The number of single character edits to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
         }
-        _ => (),
     }
-    match typ {
-        ItemType::Pilot => match canonical.as_str() {
-            "adigallia-delta7b" => "adigallia-delta7baethersprite",
-            "ahsokatano-awing" => "ahsokatano-rz1awing",
-            "blacksquadronace-t70" => "blacksquadronace-t70xwing",
-            "bossk-z95headhunter" => "bossk-z95af4headhunter",
-            "chewbacca-resistance" => "chewbacca-scavengedyt1300",
-            "corranhorn-xwing" => "corranhorn-t65xwing",
-            "dalanoberos-starviper" => "dalanoberos-starviperclassattackplatform",
-            "darthvader-tiedefender" => "darthvader-tieddefender",
-            "durge-separatist" => "durge-separatistalliance",
-            "ezrabridger-sheathipede" => "ezrabridger-sheathipedeclassshuttle",
-            "ezrabridger-tiefighter" => "ezrabridger-tielnfighter",
-            "fennrau-sheathipede" => "fennrau-sheathipedeclassshuttle",
-            "garvendreis-xwing" => "garvendreis-t65xwing",
-            "gideonhask-tieinterceptor" => "gideonhask-tieininterceptor",
-            "hansolo-resistance" => "hansolo-scavengedyt1300",
-            "herasyndulla-awing" => "herasyndulla-rz1awing",
-            "herasyndulla-bwing" => "herasyndulla-asf01bwing",
-            "herasyndulla-vcx100" => "herasyndulla-vcx100lightfreighter",
-            "landocalrissian-resistance" => "landocalrissian-scavengedyt1300",
-            "norrawexley-ywing" => "norrawexley-btla4ywing",
-            "poedameron-yt1300" => "poedameron-scavengedyt1300",
-            "sabinewren-awing" => "sabinewren-rz1awing",
-            "sabinewren-scum" => "sabinewren-lancerclasspursuitcraft",
-            "sabinewren-tiefighter" => "sabinewren-tielnfighter",
-            "sharabey-awing" => "sharabey-rz1awing",
-            "vultskerris-tieinterceptor" => "vultskerris-tieininterceptor",
-            "wedgeantilles-awing" => "wedgeantilles-rz1awing",
-            "zeborrelios-sheathipede" => "zeborrelios-sheathipedeclassshuttle",
-            "zeborrelios-tiefighter" => "zeborrelios-tielnfighter",
-            x => x,
-        },
-        ItemType::Upgrade => match canonical.as_str() {
-            "b6bladewingprototype-epic" => "b6bladewingprototype-command",
-            "c3po-resistance" => "c3po-crew",
-            "chewbacca-resistance" => "chewbacca-crew",
-            "chopper-astromech" => "chopper",
-            "hansolo-resistance" => "hansolo-crew",
-            "rey" => "rey-gunner",
-            "vectoredcannons-rz1" => "vectoredcannonsrz1",
-            x => x,
-        },
-        ItemType::Ship => match canonical.as_str() {
-            /* in case anyone asks: these are 1.0 ships that have been
-            renamed over time in yasb, but the old records are not
-            cleared with "Reset my Collection" */
-            "arc170"
-            | "awing"
-            | "bsf17bomber"
-            | "bwing"
-            | "firespray31"
-            | "hwk290"
-            | "kwing"
-            | "lambdaclassshuttle"
-            | "quadjumper"
-            | "starviper"
-            | "tieadvanced"
-            | "tieadvancedprototype"
-            | "tieaggressor"
-            | "tiebomber"
-            | "tiedefender"
-            | "tiefighter"
-            | "tieinterceptor"
-            | "tiesilencer"
-            | "tiestriker"
-            | "upsilonclasscommandshuttle"
-            | "uwing"
-            | "vcx100"
-            | "xwing"
-            | "yt1300-resistance"
-            | "yt1300"
-            | "yt2400"
-            | "ywing"
-            | "z95headhunter" => {
-                canonical.push_str("-legacyyasb");
-                canonical.as_str()
-            }
-            _ => canonical.as_str(),
-        },
-        _ => canonical.as_str(),
+    row[b.len()]
+}
+
+/// How far apart a canonical YASB name and an xws id are. When both have a
+/// ship suffix and one contains the other, like `-yt1300` and
+/// `-scavengedyt1300`, only the names before it are compared.
+fn distance(canonical: &str, xws: &str) -> usize {
+    match (canonical.split_once('-'), xws.split_once('-')) {
+        (Some((a, sa)), Some((b, sb))) if sa.contains(sb) || sb.contains(sa) => edit_distance(a, b),
+        _ => edit_distance(canonical, xws),
     }
-    .to_string()
+}
+
+/// Up to 3 of the `candidates` close enough to the canonical form of `name`.
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<XWS> {
+    let canonical = to_canonical(name);
+    let max = (canonical.len() / 3).max(2);
+
+    let mut close: Vec<(usize, &str)> = candidates
+        .map(|c| (distance(&canonical, c), c))
+        .filter(|(d, _)| *d <= max)
+        .collect();
+    close.sort();
+    close.dedup();
+    close
+        .into_iter()
+        .take(3)
+        .map(|(_, c)| c.to_owned())
+        .collect()
 }
 
 impl Collection {
     /// Attempts to turn YASB collection expansion names into their proper SKUs.
     /// Returns a list of any expansion names that couldn't be found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the counts aren't numbers.
     pub fn expansion_skus(
         &self,
         catalog: &expansions::Catalog,
-    ) -> (BTreeMap<String, u32>, Vec<String>) {
+    ) -> Result<(BTreeMap<String, u32>, Vec<String>), Error> {
         let mut skus = BTreeMap::new();
         let mut missing = vec![];
 
         'exp_search: for (e, c) in &self.expansions {
-            let n: u32 = c.parse().unwrap(); // FIXME:
+            let n = parse_count(e, c)?;
             if n == 0 {
                 continue;
             }
@@ -192,65 +225,216 @@ impl Collection {
             missing.push(e.to_owned())
         }
 
-        (skus, missing)
+        Ok((skus, missing))
     }
 
-    /// Does not do any checking of correctness/missing items, just tries
-    /// to use the hard-coded YASB-to-xws lookup/rules for the singles.
-    pub fn singles_as_xws(&self) -> BTreeMap<Item, u32> {
-        let mut item_counts = BTreeMap::new();
-
-        if let Collection {
-            singletons: Some(ref singles),
-            ..
-        } = self
-        {
-            for (name, c) in singles.upgrade.as_ref().unwrap_or(&HashMap::new()) {
-                let n: u32 = c.parse().unwrap(); // FIXME:
-                if n == 0 {
-                    continue;
-                }
-                let item = Item {
-                    r#type: ItemType::Upgrade,
-                    xws: to_xws(name, ItemType::Upgrade),
-                };
-                if item_counts.get(&item).is_some() {
-                    println!("YASB: ignoring duplicate item: {}", name);
-                    continue;
+    /// The singles with their type, YASB name and count.
+    fn singles(&self) -> Vec<(ItemType, &String, &String)> {
+        let mut singles = vec![];
+        if let Some(ref s) = self.singletons {
+            for (typ, map) in [
+                (ItemType::Upgrade, &s.upgrade),
+                (ItemType::Pilot, &s.pilot),
+                (ItemType::Ship, &s.ship),
+            ] {
+                for (name, count) in map.iter().flatten() {
+                    singles.push((typ, name, count));
                 }
-                item_counts.insert(item, n);
             }
-            for (name, c) in singles.pilot.as_ref().unwrap_or(&HashMap::new()) {
-                let n: u32 = c.parse().unwrap(); // FIXME:
-                if n == 0 {
-                    continue;
-                }
-                let item = Item {
-                    r#type: ItemType::Pilot,
-                    xws: to_xws(name, ItemType::Pilot),
-                };
-                if item_counts.get(&item).is_some() {
-                    println!("YASB: ignoring duplicate item: {}", name);
-                    continue;
-                }
-                item_counts.insert(item, n);
+        }
+        singles
+    }
+
+    /// Turns the singles into xws ids with the YASB-to-xws name map, and
+    /// checks them against xwing-data2. Returns the names that couldn't be
+    /// found, sorted by type and name, with suggestions for the map. Legacy
+    /// ships count as found if they can be used as current ships, and the
+    /// names the map skips are left out.
+    ///
+    /// The names that couldn't be found are still in the singles, with the
+    /// xws id they were turned into, so cards newer than the xwing-data2
+    /// checkout aren't lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the counts aren't numbers.
+    pub fn singles_as_xws(
+        &self,
+        names: &NameMap,
+        data: &Data,
+        compatibility: &Compatibility,
+    ) -> Result<(BTreeMap<Item, u32>, Vec<Unmapped>), Error> {
+        let is_ship = |xws: &str| data.get_ship_model(xws).is_some();
+
+        let mut item_counts = BTreeMap::new();
+        let mut unmapped = vec![];
+        for (typ, name, c) in self.singles() {
+            let n = parse_count(name, c)?;
+            if n == 0 {
+                continue;
             }
-            for (name, c) in singles.ship.as_ref().unwrap_or(&HashMap::new()) {
-                let n: u32 = c.parse().unwrap(); // FIXME:
-                if n == 0 {
-                    continue;
+            let xws = names.to_xws(name, typ);
+            if xws.is_empty() {
+                continue;
+            }
+            let found = match typ {
+                ItemType::Ship => {
+                    is_ship(&xws)
+                        || compatibility
+                            .models
+                            .get(&xws)
+                            .is_some_and(|ships| ships.iter().all(|s| is_ship(s)))
                 }
-                let item = Item {
-                    r#type: ItemType::Ship,
-                    xws: to_xws(name, ItemType::Ship),
+                ItemType::Pilot => data.get_pilot(&xws).is_some(),
+                _ => data.get_upgrade(&xws).is_some(),
+            };
+            if !found {
+                let suggestions = match typ {
+                    ItemType::Ship => suggest(name, data.ships.iter().map(|s| s.xws.as_str())),
+                    ItemType::Pilot => suggest(
+                        name,
+                        data.ships
+                            .iter()
+                            .flat_map(|s| &s.pilots)
+                            .map(|p| p.xws.as_str()),
+                    ),
+                    _ => suggest(name, data.upgrades.iter().map(|u| u.xws.as_str())),
                 };
-                if item_counts.get(&item).is_some() {
-                    println!("YASB: ignoring duplicate item: {}", name);
-                    continue;
-                }
-                item_counts.insert(item, n);
+                unmapped.push(Unmapped {
+                    r#type: typ,
+                    name: name.to_owned(),
+                    xws: xws.to_owned(),
+                    suggestions,
+                });
+            }
+
+            let item = Item { r#type: typ, xws };
+            if item_counts.contains_key(&item) {
+                eprintln!("YASB: ignoring duplicate item: {}", name);
+                continue;
+            }
+            item_counts.insert(item, n);
+        }
+        unmapped.sort_by(|a, b| (a.r#type, &a.name).cmp(&(b.r#type, &b.name)));
+        Ok((item_counts, unmapped))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bad_count() {
+        let coll: Collection = serde_json::from_str(
+            r#"{"expansions": {}, "singletons": {"pilot": {"Poe Dameron": "two"}}}"#,
+        )
+        .unwrap();
+
+        match coll.singles_as_xws(
+            &NameMap::default(),
+            &Data::default(),
+            &Compatibility::default(),
+        ) {
+            Err(Error::BadCount { name, count }) => {
+                assert_eq!(name, "Poe Dameron");
+                assert_eq!(count, "two");
             }
+            r => panic!("expected a bad count, got {:?}", r),
         }
-        item_counts
+    }
+
+    #[test]
+    fn test_to_xws() {
+        let mut names: NameMap = serde_json::from_str(
+            r#"{"ship": {"E-Wing": "ewing-legacyyasb", "xwing": "xwing-legacyyasb"}}"#,
+        )
+        .unwrap();
+        assert_eq!(names.to_xws("E-Wing", ItemType::Ship), "ewing-legacyyasb");
+        assert_eq!(names.to_xws("E-wing", ItemType::Ship), "ewing");
+        assert_eq!(names.to_xws("X-Wing", ItemType::Ship), "xwing-legacyyasb");
+        // the sections are per type
+        assert_eq!(names.to_xws("X-Wing", ItemType::Pilot), "xwing");
+
+        names.extend(toml::from_str("[ship]\nxwing = \"t65xwing\"\n").unwrap());
+        assert_eq!(names.to_xws("X-Wing", ItemType::Ship), "t65xwing");
+        assert_eq!(names.to_xws("E-Wing", ItemType::Ship), "ewing-legacyyasb");
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_embedded() {
+        let names = NameMap::load_embedded().unwrap();
+        assert_eq!(
+            names.to_xws("Poe Dameron (YT-1300)", ItemType::Pilot),
+            "poedameron-scavengedyt1300"
+        );
+        assert_eq!(names.to_xws("Rey", ItemType::Upgrade), "rey-gunner");
+        assert_eq!(names.to_xws("E-Wing", ItemType::Ship), "");
+    }
+
+    #[test]
+    fn test_singles_as_xws() {
+        use crate::xwingdata2::fixtures::{pilot, ship};
+
+        let coll: Collection = serde_json::from_str(
+            r#"{"expansions": {}, "singletons": {
+                "ship": {"X-Wing": "1", "TIE Fighter": "1", "E-Wing": "1"},
+                "pilot": {"Luke Skywalker": "1", "Jek Porkins": "0"}
+            }}"#,
+        )
+        .unwrap();
+        let names: NameMap = serde_json::from_str(
+            r#"{"ship": {"xwing": "xwing-legacyyasb", "tiefighter": "tiefighter-legacyyasb",
+                "E-Wing": ""}}"#,
+        )
+        .unwrap();
+        let compatibility: Compatibility =
+            serde_json::from_str(r#"{"xwing-legacyyasb": ["t65xwing"]}"#).unwrap();
+        let data = Data {
+            ships: vec![ship(
+                "T-65 X-wing",
+                "t65xwing",
+                "rebelalliance",
+                vec![pilot("lukeskywalker")],
+            )],
+            ..Default::default()
+        };
+
+        let (singles, unmapped) = coll.singles_as_xws(&names, &data, &compatibility).unwrap();
+        // the not found ship is still counted, the skipped one isn't
+        assert_eq!(singles.len(), 3);
+        assert_eq!(
+            singles.get(&Item::new(ItemType::Ship, "tiefighter-legacyyasb")),
+            Some(&1)
+        );
+        assert_eq!(
+            unmapped,
+            vec![Unmapped {
+                r#type: ItemType::Ship,
+                name: "TIE Fighter".to_owned(),
+                xws: "tiefighter-legacyyasb".to_owned(),
+                suggestions: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let pilots = [
+            "poedameron",
+            "poedameron-scavengedyt1300",
+            "poedameron-swz68",
+            "hansolo-scavengedyt1300",
+        ];
+        assert_eq!(
+            suggest("Poe Dameron (YT-1300)", pilots.into_iter()),
+            vec!["poedameron-scavengedyt1300"]
+        );
+        assert_eq!(
+            suggest("Poe Dameronn", pilots.into_iter()),
+            vec!["poedameron"]
+        );
+        assert!(suggest("Wedge Antilles", pilots.into_iter()).is_empty());
     }
 }